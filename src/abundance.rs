@@ -0,0 +1,56 @@
+// neutronics toolbox
+use ntools::iaea::{IsomerState, Nuclide};
+
+/// Natural isotopic abundances in atom %, keyed by symbol and mass number
+///
+/// Only naturally occurring radionuclides are listed, as these are the only
+/// isotopes that can have both decay data and a natural abundance. Values
+/// are the IUPAC representative isotopic compositions.
+static ABUNDANCES: [(&str, u32, f32); 30] = [
+    ("K", 40, 0.0117),
+    ("Ca", 48, 0.187),
+    ("V", 50, 0.250),
+    ("Ge", 76, 7.75),
+    ("Se", 82, 8.82),
+    ("Kr", 78, 0.355),
+    ("Rb", 87, 27.83),
+    ("Zr", 96, 2.80),
+    ("Mo", 100, 9.744),
+    ("Cd", 113, 12.225),
+    ("In", 115, 95.71),
+    ("Te", 128, 31.74),
+    ("Te", 130, 34.08),
+    ("Ba", 130, 0.106),
+    ("La", 138, 0.08881),
+    ("Nd", 144, 23.798),
+    ("Nd", 150, 5.638),
+    ("Sm", 147, 14.99),
+    ("Sm", 148, 11.24),
+    ("Eu", 151, 47.81),
+    ("Gd", 152, 0.20),
+    ("Lu", 176, 2.599),
+    ("Hf", 174, 0.16),
+    ("Re", 187, 62.60),
+    ("Os", 186, 1.59),
+    ("Bi", 209, 100.0),
+    ("Th", 232, 100.0),
+    ("U", 234, 0.0054),
+    ("U", 235, 0.7204),
+    ("U", 238, 99.2742),
+];
+
+/// Natural abundance of a nuclide as a fraction, if it occurs naturally
+///
+/// Excited states are never considered naturally occurring.
+pub fn fraction(nuclide: &Nuclide) -> Option<f32> {
+    if matches!(nuclide.state, IsomerState::Excited(_)) {
+        return None;
+    }
+
+    ABUNDANCES
+        .iter()
+        .find(|(symbol, isotope, _)| {
+            symbol.eq_ignore_ascii_case(&nuclide.symbol) && *isotope == nuclide.isotope
+        })
+        .map(|(_, _, percent)| percent * 1e-2)
+}
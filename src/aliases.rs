@@ -0,0 +1,185 @@
+// other
+use log::debug;
+
+/// Number of elements, which are listed first and in order of atomic number
+const ELEMENTS: usize = 118;
+
+/// Full element names, including alternate spellings, mapped to their symbol
+static ELEMENT_NAMES: [(&str, &str); 124] = [
+    ("hydrogen", "h"),
+    ("helium", "he"),
+    ("lithium", "li"),
+    ("beryllium", "be"),
+    ("boron", "b"),
+    ("carbon", "c"),
+    ("nitrogen", "n"),
+    ("oxygen", "o"),
+    ("fluorine", "f"),
+    ("neon", "ne"),
+    ("sodium", "na"),
+    ("magnesium", "mg"),
+    ("aluminium", "al"),
+    ("silicon", "si"),
+    ("phosphorus", "p"),
+    ("sulfur", "s"),
+    ("chlorine", "cl"),
+    ("argon", "ar"),
+    ("potassium", "k"),
+    ("calcium", "ca"),
+    ("scandium", "sc"),
+    ("titanium", "ti"),
+    ("vanadium", "v"),
+    ("chromium", "cr"),
+    ("manganese", "mn"),
+    ("iron", "fe"),
+    ("cobalt", "co"),
+    ("nickel", "ni"),
+    ("copper", "cu"),
+    ("zinc", "zn"),
+    ("gallium", "ga"),
+    ("germanium", "ge"),
+    ("arsenic", "as"),
+    ("selenium", "se"),
+    ("bromine", "br"),
+    ("krypton", "kr"),
+    ("rubidium", "rb"),
+    ("strontium", "sr"),
+    ("yttrium", "y"),
+    ("zirconium", "zr"),
+    ("niobium", "nb"),
+    ("molybdenum", "mo"),
+    ("technetium", "tc"),
+    ("ruthenium", "ru"),
+    ("rhodium", "rh"),
+    ("palladium", "pd"),
+    ("silver", "ag"),
+    ("cadmium", "cd"),
+    ("indium", "in"),
+    ("tin", "sn"),
+    ("antimony", "sb"),
+    ("tellurium", "te"),
+    ("iodine", "i"),
+    ("xenon", "xe"),
+    ("caesium", "cs"),
+    ("barium", "ba"),
+    ("lanthanum", "la"),
+    ("cerium", "ce"),
+    ("praseodymium", "pr"),
+    ("neodymium", "nd"),
+    ("promethium", "pm"),
+    ("samarium", "sm"),
+    ("europium", "eu"),
+    ("gadolinium", "gd"),
+    ("terbium", "tb"),
+    ("dysprosium", "dy"),
+    ("holmium", "ho"),
+    ("erbium", "er"),
+    ("thulium", "tm"),
+    ("ytterbium", "yb"),
+    ("lutetium", "lu"),
+    ("hafnium", "hf"),
+    ("tantalum", "ta"),
+    ("tungsten", "w"),
+    ("rhenium", "re"),
+    ("osmium", "os"),
+    ("iridium", "ir"),
+    ("platinum", "pt"),
+    ("gold", "au"),
+    ("mercury", "hg"),
+    ("thallium", "tl"),
+    ("lead", "pb"),
+    ("bismuth", "bi"),
+    ("polonium", "po"),
+    ("astatine", "at"),
+    ("radon", "rn"),
+    ("francium", "fr"),
+    ("radium", "ra"),
+    ("actinium", "ac"),
+    ("thorium", "th"),
+    ("protactinium", "pa"),
+    ("uranium", "u"),
+    ("neptunium", "np"),
+    ("plutonium", "pu"),
+    ("americium", "am"),
+    ("curium", "cm"),
+    ("berkelium", "bk"),
+    ("californium", "cf"),
+    ("einsteinium", "es"),
+    ("fermium", "fm"),
+    ("mendelevium", "md"),
+    ("nobelium", "no"),
+    ("lawrencium", "lr"),
+    ("rutherfordium", "rf"),
+    ("dubnium", "db"),
+    ("seaborgium", "sg"),
+    ("bohrium", "bh"),
+    ("hassium", "hs"),
+    ("meitnerium", "mt"),
+    ("darmstadtium", "ds"),
+    ("roentgenium", "rg"),
+    ("copernicium", "cn"),
+    ("nihonium", "nh"),
+    ("flerovium", "fl"),
+    ("moscovium", "mc"),
+    ("livermorium", "lv"),
+    ("tennessine", "ts"),
+    ("oganesson", "og"),
+    ("aluminum", "al"),
+    ("sulphur", "s"),
+    ("cesium", "cs"),
+    ("wolfram", "w"),
+    ("niton", "rn"),
+    ("columbium", "nb"),
+];
+
+/// Whole inputs with a conventional name of their own
+static SPECIAL_NAMES: [(&str, &str); 2] = [("tritium", "h3"), ("radiocarbon", "c14")];
+
+/// Atomic number of an element symbol, e.g. 27 for "Co"
+pub fn atomic_number(symbol: &str) -> Option<u32> {
+    let symbol = symbol.to_lowercase();
+    ELEMENT_NAMES[..ELEMENTS]
+        .iter()
+        .position(|(_, s)| *s == symbol)
+        .map(|i| i as u32 + 1)
+}
+
+/// Map alternate spellings of a nuclide onto the notation the parser expects
+///
+/// Covers full element names (e.g. "cobalt-60", "cesium137"), a few special
+/// names such as "tritium", and the legacy "g" suffix for ground states (e.g.
+/// "co60g"). Anything else is returned unchanged.
+pub fn canonical(input: &str) -> String {
+    let lowercase = input.trim().to_lowercase();
+
+    if let Some((_, name)) = SPECIAL_NAMES.iter().find(|(alias, _)| *alias == lowercase) {
+        debug!("Using \"{name}\" for \"{input}\"");
+        return name.to_string();
+    }
+
+    // split the element name from the mass number, state, or range
+    let split = lowercase
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(lowercase.len());
+    let (element, rest) = lowercase.split_at(split);
+
+    let symbol = ELEMENT_NAMES
+        .iter()
+        .find(|(name, _)| *name == element)
+        .map_or(element, |(_, symbol)| symbol);
+
+    // ground states were often marked with a trailing "g"
+    let rest = match rest.strip_suffix('g') {
+        Some(mass) if mass.ends_with(|c: char| c.is_ascii_digit()) => format!("{mass}m0"),
+        _ => rest.to_string(),
+    };
+
+    let canonical = format!("{symbol}{rest}");
+    match canonical == lowercase {
+        true => input.to_string(),
+        false => {
+            debug!("Using \"{canonical}\" for \"{input}\"");
+            canonical
+        }
+    }
+}
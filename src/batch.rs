@@ -0,0 +1,89 @@
+// standard lib
+use std::fs;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::f;
+
+// other
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// A single independent query from a --batch file
+///
+/// Every job is run as if its options were given on the command line, so
+/// anything not set here takes the usual default or config file value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchJob {
+    /// Label for log messages and the default output name
+    pub name: Option<String>,
+    /// Nuclides to query, in any form accepted on the command line
+    pub nuclides: Vec<String>,
+    /// Radiation types, e.g. ["gamma", "xray"]
+    pub rad: Vec<String>,
+    /// Output file name without the extension
+    pub output: Option<String>,
+    /// Output formats, e.g. ["json", "mcnp"]
+    pub format: Vec<String>,
+    /// Any other options, e.g. ["--sort", "i", "--precision", "3"]
+    pub args: Vec<String>,
+}
+
+/// Top level of a TOML batch file, a list of `[[job]]` tables
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BatchFile {
+    job: Vec<BatchJob>,
+}
+
+impl BatchJob {
+    /// Name of the job for log messages, the job number if not named
+    pub fn label(&self, number: usize) -> String {
+        self.name.clone().unwrap_or_else(|| f!("job{number}"))
+    }
+
+    /// Equivalent command line arguments for the job
+    ///
+    /// The output defaults to the job label so that jobs do not overwrite
+    /// each other.
+    pub fn args(&self, number: usize) -> Vec<String> {
+        let mut args = vec!["ddata".to_string()];
+        args.extend(self.nuclides.iter().cloned());
+
+        if !self.rad.is_empty() {
+            args.extend(["--rad".to_string(), self.rad.join(",")]);
+        }
+
+        let output = self.output.clone().unwrap_or_else(|| self.label(number));
+        args.extend(["--output".to_string(), output]);
+
+        if !self.format.is_empty() {
+            args.extend(["--format".to_string(), self.format.join(",")]);
+        }
+
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
+/// Read the jobs from a batch file
+///
+/// A file with a `.json` extension is an array of jobs, and anything else is
+/// read as TOML with a `[[job]]` table for each.
+pub fn load(path: &Path) -> Result<Vec<BatchJob>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| f!("Unable to read batch file \"{}\"", path.display()))?;
+
+    let jobs = match path.extension().is_some_and(|e| e == "json") {
+        true => serde_json::from_str::<Vec<BatchJob>>(&text)
+            .with_context(|| f!("Invalid batch file \"{}\"", path.display()))?,
+        false => {
+            toml::from_str::<BatchFile>(&text)
+                .with_context(|| f!("Invalid batch file \"{}\"", path.display()))?
+                .job
+        }
+    };
+
+    Ok(jobs)
+}
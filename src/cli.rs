@@ -1,13 +1,26 @@
 // internal
-use crate::wrappers::{CliRadType, Property};
+use crate::nuclide::MAX_BINS;
+use crate::wrappers::{
+    CliRadType, Column, EnergyUnit, GroupStructure, IntensityUnits, McnpDist, NuclideOrder,
+    OutputFormat, Property, TableTheme,
+};
+
+// standard lib
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 // command line modules
 use clap::builder::styling::{AnsiColor, Effects};
 use clap::builder::Styles;
-use clap::{arg, Parser};
+use clap::parser::ValueSource;
+use clap::{arg, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 
 // other
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+use colored::{Color, Colorize};
+use log::{Level, LevelFilter};
+use serde::Deserialize;
 
 /// Retrieve decay data from the IAEA chart of nuclides
 ///
@@ -21,15 +34,28 @@ use anyhow::Result;
 ///     $ ddata co60 Co60 CO60 Co60m0 => Ground state Co60
 ///     $ ddata co60m co60m1 co60*    => First excited state Co60
 ///     $ ddata co                    => All Co ground state isotopes
+///     $ ddata co --include-isomers  => All Co isotopes and isomers
+///     $ ddata cs:130-140            => Cs isotopes with A = 130 to 140
 ///
 ///  Writing data to files:
-///     $ ddata <nuclides> --text  => Ascii tables
-///     $ ddata <nuclides> --json  => JSON file
-///     $ ddata <nuclides> --mcnp  => MCNP cards
+///     $ ddata <nuclides> --text     => Ascii tables
+///     $ ddata <nuclides> --json     => JSON file
+///     $ ddata <nuclides> --yaml     => YAML file
+///     $ ddata <nuclides> --toml     => TOML file
+///     $ ddata <nuclides> --html     => HTML report
+///     $ ddata <nuclides> --mcnp     => MCNP cards
+///     $ ddata <nuclides> --serpent  => Serpent 2 sources
+///     $ ddata <nuclides> --tripoli  => TRIPOLI-4 spectra
+///     $ ddata <nuclides> --fluka    => FLUKA spectra
+///     $ ddata <nuclides> --origen   => ORIGEN spectra
+///     $ ddata <nuclides> --geant4   => Geant4 GPS macro
+///     $ ddata <nuclides> --format json,mcnp => Multiple formats
 ///
 ///  Sort decay data:
 ///     $ ddata <nuclides> --sort energy     => Ascending energy
 ///     $ ddata <nuclides> --sort intensity  => Descending intensity
+///     $ ddata <nuclides> --sort parent     => Ascending parent energy
+///     $ ddata <nuclides> --sort energy --reverse => Descending energy
 ///
 ///  Choose radiation type (default: Gamma):
 ///     $ ddata <nuclides> --rad gamma      => Gamma + X-ray
@@ -38,10 +64,34 @@ use anyhow::Result;
 ///     $ ddata <nuclides> --rad beta-plus  => b+ decay
 ///     $ ddata <nuclides> --rad beta-minus => b- decay
 ///     $ ddata <nuclides> --rad electron   => Auger/conversion electron
+///     $ ddata <nuclides> --rad gamma,xray => Multiple types
+///
+///  Match nuclides with wildcards (quoted for the shell):
+///     $ ddata 'cs*'   => All caesium isotopes
+///     $ ddata '*137'  => All nuclides with A = 137
+///
+///  List nuclides with data:
+///     $ ddata --list-available --rad alpha
+///
+///  Report the decay data in use:
+///     $ ddata --data-info
+///     $ ddata --data-info --fetch
+///
+///  Include decay chain progeny:
+///     $ ddata <nuclides> --chain
+///     $ ddata <nuclides> --chain --chain-depth 3
+///
+///  Convert previous JSON output:
+///     $ ddata --from-json decay_data.json --mcnp
+///
+///  Choose table columns:
+///     $ ddata <nuclides> --columns energy,intensity
 ///
 ///  Choose output file name:
 ///     $ ddata <nuclides> --mcnp --text --json --output my_file
 ///       |_ creates 'my_file.i', 'my_file.txt', 'my_file.json'
+///     $ ddata <nuclides> --json --output my_file --output-dir results
+///       |_ creates 'results/my_file.json'
 ///
 /// Notes
 /// -----
@@ -55,10 +105,11 @@ use anyhow::Result;
 /// up to date and extremely fast. However, '--fetch' can retrieve decay data
 /// directly from the IAEA API.
 ///
-/// IAEA records with missing or unobserved intensities are included.
+/// IAEA records with missing or unobserved intensities are included unless
+/// --drop-unobserved is used.
 ///
 /// If your terminal does not support ANSI colour, this can be turned off with
-/// the --no-colour option.
+/// the --no-colour option or by setting NO_COLOR.
 #[derive(Parser)]
 #[command(
     verbatim_doc_comment,
@@ -72,10 +123,14 @@ use anyhow::Result;
 pub struct Cli {
     // * Positional
     /// List of nuclide names
+    ///
+    /// Nuclides may be given as a name (e.g. "co60", "co60m1"), an element
+    /// (e.g. "co"), an element with a mass number range (e.g. "cs:130-140"),
+    /// or a quoted wildcard pattern (e.g. "cs*", "*137", "co5?").
     #[arg(name = "nuclides")]
     pub nuclides: Vec<String>,
 
-    /// Type of decay radiation
+    /// Type(s) of decay radiation
     ///
     /// The IAEA chart of nuclides contains the following:
     ///   > Alpha ("a")
@@ -84,19 +139,29 @@ pub struct Cli {
     ///   > Gamma decay ("g") [Default]
     ///   > Auger and conversion electron ("e")
     ///   > X-ray ("x")
+    ///   > Every type above ("all")
+    ///
+    /// Multiple types may be combined as a comma separated
+    /// list, e.g. "--rad gamma,electron". Records are labelled
+    /// by radiation type in the table and JSON outputs.
     #[arg(help_heading("Data options"))]
     #[arg(short, long, value_enum)]
+    #[arg(value_delimiter = ',')]
     #[arg(hide_default_value(true))]
-    #[arg(default_value_t = CliRadType::Gamma)]
+    #[arg(default_values_t = [CliRadType::Gamma])]
     #[arg(verbatim_doc_comment)]
     #[arg(value_name = "rad")]
-    pub rad: CliRadType,
+    pub rad: Vec<CliRadType>,
 
-    /// Sort records by property ['energy', 'intensity']
+    /// Sort records by property ['energy', 'intensity', 'parent', 'none']
     ///
     /// Defaults to sorting decay data by ascending energy ('e' or 'energy').
     /// Alternatively, data may be sorted in descending order of relative
-    /// intensity with 'i' or 'intensity'.
+    /// intensity with 'i' or 'intensity', or grouped by ascending parent
+    /// (isomeric state) energy with 'p' or 'parent'.
+    ///
+    /// Use 'none' ('o' or 'original') to keep the order the IAEA gives them
+    /// in, e.g. for debugging or exact reproduction of the source data.
     #[arg(help_heading("Data options"))]
     #[arg(short, long)]
     #[arg(value_name = "property")]
@@ -104,20 +169,357 @@ pub struct Cli {
     #[arg(default_value = "energy")]
     pub sort: Property,
 
+    /// Reverse the sort order of records
+    ///
+    /// Inverts whichever --sort order is used, i.e. descending energy or
+    /// ascending intensity.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Order of the nuclides
+    ///
+    /// One of the following:
+    ///   > name (symbol, then mass number)
+    ///   > z (atomic number, then mass number)
+    ///   > mass ("a", mass number, then atomic number)
+    ///
+    /// Mass numbers are always compared as numbers, so Co60 comes before
+    /// Co108. Excited states follow their ground state.
+    #[arg(help_heading("Data options"))]
+    #[arg(long, value_enum)]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = NuclideOrder::Name)]
+    #[arg(verbatim_doc_comment)]
+    #[arg(value_name = "order")]
+    pub nuclide_order: NuclideOrder,
+
     /// Query IAEA directly rather than pre-fetched data
     ///
     /// Note that this requires and internet connection and will be much slower
-    /// than using pre-processed data.
+    /// than using pre-processed data. A progress bar is shown unless running
+    /// with --quiet or --verbose.
     #[arg(help_heading("Data options"))]
     #[arg(long)]
     pub fetch: bool,
 
+    /// Number of retries for failed IAEA requests
+    ///
+    /// Defaults to 3. Only applies with --fetch. The delay between retries
+    /// doubles every time, and pre-fetched data are used if every attempt
+    /// fails.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "3")]
+    pub retries: u32,
+
+    /// Initial delay between retries in milliseconds
+    ///
+    /// Defaults to 500 ms, doubled after each retry.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "ms")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "500")]
+    pub retry_delay: u64,
+
+    /// Error on unknown nuclides or any without data
+    ///
+    /// By default, unknown nuclides and those without decay data for --rad
+    /// are ignored with a warning at most. This makes any of them an error
+    /// that lists every offending input, so that typos such as "co06" cannot
+    /// silently produce partial output.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Nuclides to remove after expanding the inputs
+    ///
+    /// A comma separated list of nuclide names, e.g. "--exclude cs137,cs134"
+    /// to get every other caesium isotope from "cs". Names are matched to the
+    /// expanded nuclides in the same way as the inputs.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_delimiter = ',')]
+    #[arg(value_name = "list")]
+    pub exclude: Vec<String>,
+
+    /// Maximum number of nuclides to fetch without --yes
+    ///
+    /// Bare elements can expand to dozens of nuclides, each needing a request
+    /// to the IAEA API. Anything over this limit with --fetch is an error
+    /// unless confirmed with --yes. Defaults to 50, and does not apply to the
+    /// pre-fetched data. This is checked before anything is fetched for each
+    /// nuclide, including excited states and decay chains.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "50")]
+    pub max_nuclides: usize,
+
+    /// Confirm fetching more than --max-nuclides
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub yes: bool,
+
+    /// Expand elements to excited states as well
+    ///
+    /// By default a bare element (e.g. "co") only expands to ground state
+    /// isotopes. This also includes every excited state with decay data.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub include_isomers: bool,
+
+    /// Only keep ground state nuclides
+    ///
+    /// Removes any excited states, e.g. after expanding a wildcard or decay
+    /// chain.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(conflicts_with = "only_isomers")]
+    pub only_ground: bool,
+
+    /// Only keep excited state nuclides
+    ///
+    /// Removes any ground states. Bare elements only expand to ground states,
+    /// so combine with --include-isomers to keep their excited states.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub only_isomers: bool,
+
+    /// Minimum half-life of expanded excited states
+    ///
+    /// Only applies with --include-isomers. Excited states with a half-life
+    /// below this many seconds are skipped when expanding elements, while
+    /// ground states are always kept.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "seconds")]
+    pub isomer_halflife_min: Option<f32>,
+
+    /// Explain which parent energy was selected
+    ///
+    /// Logs the parent energy chosen for each nuclide and radiation type, and
+    /// why. This includes cases where there is no ground state and the first
+    /// parent energy is assumed to be the first excited state.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Remove records with no intensity
+    ///
+    /// IAEA records with missing or unobserved intensities are included by
+    /// default. This keeps only the quantified lines in the table and every
+    /// output file.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub drop_unobserved: bool,
+
+    /// Merge lines closer together than this energy
+    ///
+    /// Collapses lines of the same radiation type within the given energy of
+    /// each other into a single line, with the summed intensity and the
+    /// intensity-weighted mean energy. Useful for lines that a detector cannot
+    /// resolve. The tolerance is in keV unless set by --energy-unit.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "energy")]
+    pub merge_duplicates: Option<f32>,
+
+    /// Warn about energies listed more than once
+    ///
+    /// Records of the same radiation type with identical energies may be
+    /// unresolved doublets or issues with the data. These are always logged at
+    /// DEBUG level, but are warnings with this flag. Combine with
+    /// --merge-duplicates to collapse them.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub warn_duplicates: bool,
+
+    /// Include the decay chain of each nuclide
+    ///
+    /// Follows the daughters of every requested nuclide down to stable
+    /// progeny, adding each as its own entry. Daughter intensities are scaled
+    /// by the cumulative branching ratio from the requested parent.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub chain: bool,
+
+    /// Maximum number of generations for --chain
+    ///
+    /// Defaults to 10.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "10")]
+    pub chain_depth: usize,
+
+    /// List nuclides with data for the radiation type(s)
+    ///
+    /// Prints the sorted names of every nuclide with decay data for --rad and
+    /// exits. No nuclides need to be given. Combine with --json to print a
+    /// JSON array instead, or --fetch to query the IAEA directly.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub list_available: bool,
+
+    /// Choose nuclides from a list of those available
+    ///
+    /// Presents a filterable list of every nuclide with data for --rad, from
+    /// which any number may be selected. Nuclides given on the command line
+    /// are treated as elements that narrow down the list. Ignored if not
+    /// running in a terminal.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// Print the provenance of the decay data and exit
+    ///
+    /// Reports the version of ddata, the snapshot of the pre-fetched data if
+    /// recorded at build time, and the number of nuclides available for each
+    /// radiation type. Combine with --fetch to compare the pre-fetched nuclide
+    /// list against the IAEA API.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub data_info: bool,
+
+    /// Warn if total intensities are not 100 +/- percent
+    ///
+    /// Checks that the intensities of every nuclide sum to within the given
+    /// percentage of 100%, e.g. "--norm-tolerance 5", to help catch
+    /// incomplete decay data.
+    ///
+    /// Note that some radiation types legitimately exceed 100% (e.g. Co60
+    /// emits two gammas per decay), so this is off by default.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "percent")]
+    pub norm_tolerance: Option<f64>,
+
+    /// Scale intensities to sum to 100% per nuclide
+    ///
+    /// Useful when the data are used directly as emission probabilities.
+    /// Records with no intensity are left as they are.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub renormalize: bool,
+
+    /// Scale intensities relative to the strongest line
+    ///
+    /// The most intense line of every nuclide is set to 100%, and all others
+    /// are relative to it. This is distinct from --renormalize, which scales
+    /// the sum of intensities to 100%. Records with no intensity are left as
+    /// they are.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(conflicts_with = "renormalize")]
+    pub relative: bool,
+
+    /// Sum every nuclide into a single mixture
+    ///
+    /// All records are combined into one entry named "mixture", assuming an
+    /// equal activity of each nuclide unless set with --weights. The mixture
+    /// is used for the table and all output files.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub sum: bool,
+
+    /// Relative activity of each nuclide in the --sum mixture
+    ///
+    /// A comma separated list of `nuclide:weight` pairs, e.g.
+    /// "--weights co60:1.0,cs137:0.5". Intensities of each nuclide are
+    /// multiplied by the weight, and any nuclide not listed has a weight of 1.
+    /// Weights must be finite and not negative.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_delimiter = ',')]
+    #[arg(value_name = "list")]
+    #[arg(requires = "sum")]
+    pub weights: Vec<String>,
+
+    /// Weight intensities by natural isotopic abundance
+    ///
+    /// Every intensity is multiplied by the fractional natural abundance of
+    /// the nuclide, and anything not naturally occurring is removed. This is
+    /// most useful with bare elements, e.g. "u" for natural uranium.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    pub natural: bool,
+
+    /// Re-process decay data from a previous JSON output
+    ///
+    /// Skips the IAEA data entirely and reads nuclide data from a file written
+    /// by --json, e.g. to convert JSON to MCNP cards offline. Any nuclides
+    /// given on the command line are ignored.
+    ///
+    /// Only energies, intensities, radiation types, and parent energies are
+    /// recovered.
+    #[arg(help_heading("Data options"))]
+    #[arg(long)]
+    #[arg(value_name = "file")]
+    pub from_json: Option<String>,
+
+    /// Units of reported intensities
+    ///
+    /// Either "percent" (default) or "fraction" per decay. Applies to the
+    /// table and data outputs. Source definitions such as MCNP cards are
+    /// always normalised per decay already, and are unaffected.
+    #[arg(help_heading("Data options"))]
+    #[arg(long, value_enum)]
+    #[arg(value_name = "units")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = IntensityUnits::Percent)]
+    pub intensity_units: IntensityUnits,
+
+    /// Units of energies given on the command line
+    ///
+    /// Either "eV", "keV" (default), or "MeV". Applies to --bin-width,
+    /// --merge-duplicates, --diff-tolerance, and --shell-tolerance, which are
+    /// converted to keV before use. Energies in the outputs are unaffected.
+    #[arg(help_heading("Data options"))]
+    #[arg(long, value_enum)]
+    #[arg(ignore_case(true))]
+    #[arg(value_name = "unit")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = EnergyUnit::Kev)]
+    pub energy_unit: EnergyUnit,
+
+    /// Units of reported energies
+    ///
+    /// Either "eV", "keV" (default), or "MeV". Applies to the table and data
+    /// outputs such as JSON and CSV. Source definitions such as MCNP cards
+    /// always use the units expected by the code, and are unaffected.
+    #[arg(help_heading("Data options"))]
+    #[arg(long, value_enum)]
+    #[arg(ignore_case(true))]
+    #[arg(value_name = "unit")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = EnergyUnit::Kev)]
+    pub out_energy_unit: EnergyUnit,
+
+    /// Source activity in Bq for emission rates
+    ///
+    /// When set, the emission rate of every line (intensity/100 * activity)
+    /// is added to the table and JSON outputs in particles/s. Lines without a
+    /// known intensity have no emission rate.
+    #[arg(help_heading("Data options"))]
+    #[arg(short, long)]
+    #[arg(value_name = "Bq")]
+    pub activity: Option<f64>,
+
     /// Prefix for output files
     ///
     /// Defaults to `decay_data`.
     ///
     /// Files are named `<output>.<ext>` and will automatically append the
     /// appropriate extension for the requested file format.
+    ///
+    /// If no output formats are requested, a recognised extension chooses the
+    /// format instead, e.g. `--output results.json` for JSON or `.i` for MCNP.
     #[arg(help_heading("Output files"))]
     #[arg(short, long)]
     #[arg(value_name = "name")]
@@ -125,6 +527,76 @@ pub struct Cli {
     #[arg(default_value = "decay_data")]
     pub output: String,
 
+    /// Directory for output files
+    ///
+    /// Output files are written as `<dir>/<output>.<ext>`, and the directory
+    /// is created if it does not exist. Defaults to the working directory, or
+    /// any directory given as part of --output.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "dir")]
+    pub output_dir: Option<String>,
+
+    /// Report output files without writing them
+    ///
+    /// Logs every file that would be created, along with the number of
+    /// nuclides and records involved. The table is still printed unless
+    /// using --quiet.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Write a separate set of files for each nuclide
+    ///
+    /// Every enabled output is written to `<output>_<nuclide>.<ext>` rather
+    /// than a single combined file.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub split: bool,
+
+    /// Write a separate set of files for each radiation type
+    ///
+    /// Every enabled output is written to `<output>.<rad>.<ext>`, e.g.
+    /// `decay_data.gamma.json` and `decay_data.xray.json`, rather than a
+    /// single combined file. May be used along with --split.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub per_rad_files: bool,
+
+    /// Gzip the text based output files
+    ///
+    /// Applies to every text based output, including the source definitions
+    /// and normalisation report, which are written as `<output>.<ext>.gz`. The
+    /// binary NumPy archive is unaffected.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub compress: bool,
+
+    /// Append to existing output files rather than overwriting them
+    ///
+    /// Only supported for the text, CSV, and MCNP outputs, as appending to
+    /// structured formats such as JSON would make them invalid. Any other
+    /// output format is an error. The processed CSV header is only written
+    /// to a new or empty file.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub append: bool,
+
+    /// Output formats as a comma separated list
+    ///
+    /// An alternative to the individual flags, combined with any that are
+    /// also given, e.g. "--format json,mcnp,text". The formats are:
+    ///   > text, json, ndjson, flat-json, yaml, toml, html
+    ///   > mcnp, serpent, tripoli, fluka, origen, geant4
+    ///   > npz (NumPy arrays)
+    ///   > records (processed CSV), tsv, csv (raw IAEA CSV)
+    #[arg(help_heading("Output files"))]
+    #[arg(long, value_enum)]
+    #[arg(value_delimiter = ',')]
+    #[arg(verbatim_doc_comment)]
+    #[arg(value_name = "list")]
+    pub format: Vec<OutputFormat>,
+
     /// Text based table
     #[arg(help_heading("Output files"))]
     #[arg(short, long)]
@@ -135,6 +607,65 @@ pub struct Cli {
     #[arg(short, long)]
     pub json: bool,
 
+    /// Include the raw records in JSON output
+    ///
+    /// Adds a `raw_records` list to every nuclide with each record for every
+    /// parent energy, before any filtering, sorting, or scaling. These are
+    /// always in keV and percent, exactly as provided by the IAEA.
+    ///
+    /// Also included with --ndjson and --yaml, but not --toml.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub json_raw: bool,
+
+    /// Newline-delimited JSON, one nuclide per line
+    ///
+    /// Each nuclide is written as a compact JSON object on its own line, which
+    /// is easier to stream into data pipelines than a pretty-printed array.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub ndjson: bool,
+
+    /// Flat JSON array with one object per record
+    ///
+    /// Every record is written to `<output>.flat.json` as an object of the
+    /// nuclide, radiation, energy, intensity, and parent_energy. Easier for
+    /// some consumers than the parallel arrays of --json.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub flat_json: bool,
+
+    /// Include nuclide metadata in the output files
+    ///
+    /// Adds the atomic number, mass number, isomer state, and half-life of
+    /// every nuclide so that the files are self-describing. This is a
+    /// `metadata` field in JSON, NDJSON, YAML, and TOML, a `#` comment above
+    /// the processed CSV and TSV columns, and a `c` comment in MCNP cards.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub with_metadata: bool,
+
+    /// YAML output format
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub yaml: bool,
+
+    /// TOML output format
+    ///
+    /// Nuclides are written as a `[[nuclide]]` array of tables. TOML has no
+    /// null value, so records with a missing energy or intensity are omitted.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub toml: bool,
+
+    /// HTML report
+    ///
+    /// A self-contained styled report with one table per nuclide, suitable for
+    /// sharing with anyone not using the terminal.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub html: bool,
+
     /// MCNP distribution cards
     #[arg(help_heading("Output files"))]
     #[arg(short, long)]
@@ -150,6 +681,222 @@ pub struct Cli {
     #[arg(default_value = "100")]
     pub id: usize,
 
+    /// Largest MCNP distribution number allowed
+    ///
+    /// Defaults to 999. Distribution numbers increase from --id for every
+    /// nuclide, and it is an error for any to go beyond this, e.g. to avoid
+    /// clashing with existing source definitions.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "num")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "999")]
+    pub mcnp_max_id: usize,
+
+    /// MCNP SI card distribution option ['L', 'H', 'A']
+    ///
+    /// Defaults to discrete lines ('L'). A histogram ('H') puts every line in
+    /// its own bin, with edges halfway between neighbouring lines, so the SP
+    /// card starts with a zero entry. Lines at the same energy share a bin,
+    /// and a single line is always discrete. Probability density points
+    /// ('A') use the lines directly.
+    ///
+    /// Ignored with --bin-width, which is always a histogram.
+    #[arg(help_heading("Output files"))]
+    #[arg(long, value_enum)]
+    #[arg(ignore_case = true)]
+    #[arg(value_name = "L|H|A")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "L")]
+    pub mcnp_dist: McnpDist,
+
+    /// Significant figures for MCNP and Serpent values
+    ///
+    /// Defaults to 5. Applies to energies, probabilities, and the norm
+    /// comment on every distribution.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "digits")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "5")]
+    #[arg(value_parser = clap::value_parser!(u8).range(1..))]
+    pub precision: u8,
+
+    /// Bin MCNP energies into a histogram of this width
+    ///
+    /// Intensities of all records falling in each fixed width energy bin are
+    /// summed, and the MCNP cards are written as a histogram (`si H`) over
+    /// the bin boundaries rather than discrete lines. Records with no energy
+    /// are excluded. The width is in keV unless set by --energy-unit.
+    ///
+    /// At most 100000 bins are allowed for a nuclide, so the width must be at
+    /// least 0.01 keV, and more for records spread over a wider range.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "energy")]
+    pub bin_width: Option<f32>,
+
+    /// Warn when an MCNP distribution exceeds this many lines
+    ///
+    /// Defaults to 100. Very long distributions may be rejected by MCNP, so a
+    /// warning is given for any nuclide with more valid records than this.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "100")]
+    pub mcnp_max_lines: usize,
+
+    /// Maximum line width of MCNP cards
+    ///
+    /// Defaults to 80 columns. Some MCNP versions allow up to 128 columns.
+    /// Also used for the Serpent source cards.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "cols")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "80")]
+    pub mcnp_width: usize,
+
+    /// Indent of continuation lines on MCNP cards
+    ///
+    /// Defaults to 8 spaces. Must be at least 5, as MCNP reads a line with
+    /// fewer leading blanks as a new card, and less than --mcnp-width.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "n")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "8")]
+    pub mcnp_indent: usize,
+
+    /// Maximum energy of MCNP sources in MeV
+    ///
+    /// Records above this energy are left off the MCNP cards. Histogram ('H')
+    /// and density point ('A') distributions also end at this energy, with a
+    /// zero probability entry if needed, so the source stops exactly there.
+    ///
+    /// Always in MeV, regardless of --energy-unit, and ignored with
+    /// --bin-width.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "MeV")]
+    pub mcnp_emax: Option<f32>,
+
+    /// Note to add as a comment before the MCNP cards
+    ///
+    /// Written as a block of `c` comments after the generated-by line at the
+    /// top of the cards, wrapped to --mcnp-width. Each line of a multi-line
+    /// note is a separate comment.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "text")]
+    pub mcnp_note: Option<String>,
+
+    /// Re-parse the written MCNP cards as a sanity check
+    ///
+    /// The SI/SP cards are read back after writing, and a warning is given if
+    /// the number of energies or probabilities does not match the records.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Serpent 2 source definitions
+    ///
+    /// Writes a source for each radiation type of every nuclide, with decay
+    /// energies in MeV and line intensities as the spectrum weights. Photons
+    /// are `g`, electrons `e`, and positrons `p`. Alpha records are an error,
+    /// as Serpent has no alpha source particle.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub serpent: bool,
+
+    /// TRIPOLI-4 source spectra
+    ///
+    /// Writes a SPECTRUM block for each nuclide, listing the energy points in
+    /// MeV followed by the associated probabilities.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub tripoli: bool,
+
+    /// FLUKA source spectra
+    ///
+    /// Writes a two column energy-weight spectrum per nuclide for use with a
+    /// user SOURCE routine. Energies are in GeV and weights are normalised to
+    /// sum to 1.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub fluka: bool,
+
+    /// ORIGEN photon source spectra
+    ///
+    /// Records are summed into the energy groups of --group-structure, and
+    /// written as group boundaries and intensities from high to low energy.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub origen: bool,
+
+    /// Energy group structure for ORIGEN spectra
+    ///
+    /// Defaults to the ORIGEN 18-group gamma structure ("origen-18"), which
+    /// spans 0-14 MeV. Records outside the structure are excluded.
+    #[arg(help_heading("Output files"))]
+    #[arg(long, value_enum)]
+    #[arg(value_name = "name")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = GroupStructure::Origen18)]
+    pub group_structure: GroupStructure,
+
+    /// Geant4 GPS macro commands
+    ///
+    /// Writes a `.mac` file with a GPS energy spectrum for each nuclide, as a
+    /// user histogram with a narrow bin (0.1%) just below every line, in MeV
+    /// and with the line intensities as the weights. Each nuclide and
+    /// radiation type is a separate source, with the sum of its intensities
+    /// as the relative source intensity.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub geant4: bool,
+
+    /// NumPy archive of energies and intensities
+    ///
+    /// Writes a `.npz` file with `<name>_energy` and `<name>_intensity` arrays
+    /// for every nuclide, ready for `numpy.load()`. Missing values are NaN.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub npz: bool,
+
+    /// CSV of the processed records
+    ///
+    /// Writes the filtered and sorted records to `<output>.records.csv` with
+    /// the columns nuclide, energy_keV, intensity_percent, parent_energy_keV.
+    ///
+    /// Works offline with the pre-fetched data, unlike --csv.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub export_csv: bool,
+
+    /// Tab-separated values of the processed records
+    ///
+    /// The same columns as --export-csv, written to `<output>.tsv`.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub tsv: bool,
+
+    /// Delimiter for the processed CSV
+    ///
+    /// Replaces the comma in --export-csv output, e.g. ';' for locales that
+    /// use a decimal comma. Must be a single character.
+    #[arg(help_heading("Output files"))]
+    #[arg(long, value_name = "char")]
+    pub csv_delimiter: Option<char>,
+
+    /// Prepend a UTF-8 BOM to processed CSV/TSV
+    ///
+    /// Helps Excel detect the encoding of --export-csv and --tsv files.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    pub csv_bom: bool,
+
     /// Fetch raw CSV directly (internet required)
     ///
     /// Quickly request a copy of CSV data directly from the IAEA API.
@@ -160,6 +907,238 @@ pub struct Cli {
     #[arg(long)]
     pub csv: bool,
 
+    /// Render every record through a template file
+    ///
+    /// The template is written to `<output>.out` once per record, using
+    /// {nuclide}, {radiation}, {energy}, {intensity}, {parent_energy}, and
+    /// {norm}. For example, "{nuclide},{energy},{intensity}" followed by a new
+    /// line gives a simple CSV. Literal braces are escaped as `\{`.
+    #[arg(help_heading("Output files"))]
+    #[arg(long)]
+    #[arg(value_name = "file")]
+    pub template: Option<PathBuf>,
+
+    /// Table columns to display
+    ///
+    /// A comma separated list of any of the following:
+    ///   > parent
+    ///   > mode
+    ///   > daughter
+    ///   > branching ("br")
+    ///   > energy
+    ///   > intensity
+    ///
+    /// Defaults to all columns. Columns are always shown in the above order.
+    #[arg(help_heading("Table options"))]
+    #[arg(short, long, value_enum)]
+    #[arg(value_delimiter = ',')]
+    #[arg(hide_default_value(true))]
+    #[arg(default_values_t = [
+        Column::Parent,
+        Column::Mode,
+        Column::Daughter,
+        Column::Branching,
+        Column::Energy,
+        Column::Intensity,
+    ])]
+    #[arg(verbatim_doc_comment)]
+    #[arg(value_name = "list")]
+    pub columns: Vec<Column>,
+
+    /// Combine radiation types into a single table
+    ///
+    /// Rows are the unique energies of each nuclide, with an intensity column
+    /// for every radiation type. Blank cells mean there is no line of that
+    /// type at the energy. The --columns option is ignored.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub combined: bool,
+
+    /// Colour theme for the table
+    ///
+    /// One of the following:
+    ///   > default
+    ///   > mono
+    ///   > high-contrast
+    ///
+    /// The "mono" theme keeps only bold and italic effects, which may suit
+    /// light terminals. The --no-colour flag still overrides any theme.
+    #[arg(help_heading("Table options"))]
+    #[arg(long, value_enum)]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value_t = TableTheme::Default)]
+    #[arg(verbatim_doc_comment)]
+    #[arg(value_name = "name")]
+    pub theme: TableTheme,
+
+    /// Highlight the most intense line of each nuclide
+    ///
+    /// The record with the highest intensity is shown in bold, taking the
+    /// first in the case of ties.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub highlight: bool,
+
+    /// Colour intensities relative to the strongest line
+    ///
+    /// Each intensity is coloured on a logarithmic ramp from dim grey to
+    /// bright yellow, relative to the most intense line of its nuclide, so
+    /// that strong lines are easy to spot. Only applies to the standard table,
+    /// and is turned off along with all other colour by --no-colour.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub heatmap: bool,
+
+    /// Show where every record came from
+    ///
+    /// Adds a source column to the standard table and a `source` field to the
+    /// JSON, NDJSON, YAML, and TOML outputs. Records are either "local" for the
+    /// pre-fetched data or "iaea" if fetched directly, which may be mixed
+    /// when --fetch falls back to the pre-fetched data after a failure.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub show_source: bool,
+
+    /// Hide the line count and total intensity of each nuclide
+    ///
+    /// By default every nuclide in the standard table ends with the number of
+    /// lines and the sum of their intensities, as a quick completeness check.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub no_footer: bool,
+
+    /// Width of the printed table in columns
+    ///
+    /// Nuclides are printed side by side when more than one fits within the
+    /// width. Defaults to the width of the terminal, or 80 columns when the
+    /// output is not a terminal (e.g. piped). Files are always written as a
+    /// single column.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    #[arg(value_name = "cols")]
+    pub width: Option<usize>,
+
+    /// Compare the lines of exactly two nuclides
+    ///
+    /// Prints a side by side table in place of the usual one, matching lines
+    /// that are within --diff-tolerance of each other. Lines present in only
+    /// one of the nuclides are flagged.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Energy tolerance for matching lines with --diff
+    ///
+    /// Defaults to 1 keV, otherwise in the units of --energy-unit.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    #[arg(value_name = "energy")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "1.0")]
+    pub diff_tolerance: f32,
+
+    /// Subtotal x-ray intensities by atomic shell
+    ///
+    /// Prints a table of x-ray line groups after the usual table, with the
+    /// energy range, number of lines, and total intensity of each. Shell
+    /// labels are not in the IAEA data, so lines are grouped where each is
+    /// within --shell-tolerance of the next.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    pub group_shells: bool,
+
+    /// Energy tolerance for grouping lines with --group-shells
+    ///
+    /// Defaults to 1 keV, otherwise in the units of --energy-unit.
+    #[arg(help_heading("Table options"))]
+    #[arg(long)]
+    #[arg(value_name = "energy")]
+    #[arg(hide_default_value(true))]
+    #[arg(default_value = "1.0")]
+    pub shell_tolerance: f32,
+
+    /// Print a one line summary per nuclide to stdout
+    ///
+    /// Columns are `name total_lines max_intensity_keV norm`, where
+    /// max_intensity_keV is the energy of the most intense line. This is
+    /// printed even with --quiet for use in scripts.
+    #[arg(long)]
+    pub summary: bool,
+
+    /// Print summary statistics per nuclide to stdout
+    ///
+    /// A small table of the number of lines, total intensity, and the
+    /// intensity-weighted mean energy of each nuclide. Nuclides without any
+    /// valid records are skipped.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Print the normalisation of each nuclide to stdout
+    ///
+    /// A two column table of each nuclide and the sum of its intensities in
+    /// particles/decay. This is also written to `<output>.norm` if any other
+    /// output files are being written.
+    #[arg(long)]
+    pub norm_report: bool,
+
+    /// Print one plain line per record instead of the table
+    ///
+    /// Each line is `nuclide energy intensity` separated by spaces, in the
+    /// order given by --sort, with `-` for any missing value. There is no
+    /// header or colour, and this is printed even with --quiet.
+    #[arg(long)]
+    pub raw_lines: bool,
+
+    /// Plot the spectrum of each nuclide to stdout
+    ///
+    /// A histogram of intensity against energy drawn with block characters,
+    /// binned into one column per character across --width or the terminal.
+    /// This is printed even with --quiet, and the colour is removed by
+    /// --no-colour.
+    #[arg(long)]
+    pub plot: bool,
+
+    /// Print only the number of records per nuclide
+    ///
+    /// Prints `<nuclide> <record_count>` for each nuclide to stdout, after
+    /// all filtering, and exits without the table or any output files.
+    #[arg(long)]
+    pub count_only: bool,
+
+    /// Path to a config file of default options
+    ///
+    /// Overrides the search for `decaydata.toml` in the working directory,
+    /// then `$XDG_CONFIG_HOME` (or `~/.config`). The file may set `rad`,
+    /// `sort`, `output`, and `format`, for example:
+    ///   > rad = ["gamma", "xray"]
+    ///   > sort = "intensity"
+    ///   > output = "my_data"
+    ///   > format = ["json", "mcnp"]
+    ///
+    /// Anything given on the command line takes priority.
+    #[arg(long)]
+    #[arg(value_name = "path")]
+    #[arg(verbatim_doc_comment)]
+    pub config: Option<PathBuf>,
+
+    /// Path to a file of independent queries to run in turn
+    ///
+    /// A TOML file with a `[[job]]` table per query, or a JSON array of the
+    /// same. Each job may set `name`, `nuclides`, `rad`, `output`, `format`,
+    /// and `args` for any other options, for example:
+    ///   > [[job]]
+    ///   > nuclides = ["co60", "cs137"]
+    ///   > format = ["mcnp"]
+    ///   > args = ["--sort", "i"]
+    ///
+    /// Outputs are named after the job unless `output` is set. A failed job
+    /// does not stop the rest, and all failures are reported at the end.
+    #[arg(long)]
+    #[arg(value_name = "path")]
+    #[arg(verbatim_doc_comment)]
+    #[arg(conflicts_with = "nuclides")]
+    pub batch: Option<PathBuf>,
+
     // * Flags
     /// Verbose logging (-v, -vv)
     ///
@@ -171,17 +1150,265 @@ pub struct Cli {
     pub verbose: u8,
 
     /// Supress all log output (overrules --verbose)
+    ///
+    /// Only applies to stderr, so anything is still written to --log-file.
     #[arg(short, long)]
     pub quiet: bool,
 
+    /// Also write log output to a file
+    ///
+    /// Messages are appended to the file with timestamps, at the same
+    /// verbosity as stderr.
+    #[arg(long)]
+    #[arg(value_name = "path")]
+    pub log_file: Option<PathBuf>,
+
+    /// Only write log output to --log-file, not stderr
+    #[arg(long)]
+    #[arg(requires = "log_file")]
+    pub log_file_only: bool,
+
+    /// Report the time spent in each phase
+    ///
+    /// Prints a summary of the wall-clock time spent parsing, retrieving
+    /// records, and writing every output to stderr, even with --quiet.
+    #[arg(long)]
+    pub timing: bool,
+
     /// Turn off table colours
     ///
     /// If your terminal does not support ANSI colour, this can be turned off
     /// with this --no-colour option to remove escape sequences from the stdio.
+    ///
+    /// Colour is also turned off if the NO_COLOR environment variable is set
+    /// to any non-empty value.
     #[arg(short, long)]
     pub no_colour: bool,
 }
 
+impl Cli {
+    /// Full output path prefix, combining --output-dir and --output
+    pub fn output_path(&self) -> PathBuf {
+        match &self.output_dir {
+            Some(dir) => Path::new(dir).join(&self.output),
+            None => PathBuf::from(&self.output),
+        }
+    }
+
+    /// Enable the output flags for every format given to --format
+    pub fn apply_formats(&mut self) {
+        for format in self.format.clone() {
+            match format {
+                OutputFormat::Text => self.text = true,
+                OutputFormat::Json => self.json = true,
+                OutputFormat::Ndjson => self.ndjson = true,
+                OutputFormat::FlatJson => self.flat_json = true,
+                OutputFormat::Yaml => self.yaml = true,
+                OutputFormat::Toml => self.toml = true,
+                OutputFormat::Html => self.html = true,
+                OutputFormat::Mcnp => self.mcnp = true,
+                OutputFormat::Serpent => self.serpent = true,
+                OutputFormat::Tripoli => self.tripoli = true,
+                OutputFormat::Fluka => self.fluka = true,
+                OutputFormat::Origen => self.origen = true,
+                OutputFormat::Geant4 => self.geant4 = true,
+                OutputFormat::Npz => self.npz = true,
+                OutputFormat::Records => self.export_csv = true,
+                OutputFormat::Tsv => self.tsv = true,
+                OutputFormat::Csv => self.csv = true,
+            }
+        }
+    }
+
+    /// Colour is disabled by --no-colour or a non-empty `NO_COLOR` variable
+    pub fn colour_disabled(&self) -> bool {
+        self.no_colour || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    }
+}
+
+/// Name of the config file searched for by default
+const CONFIG_NAME: &str = "decaydata.toml";
+
+/// Ids of every argument that chooses an output format
+const OUTPUT_ARGS: [&str; 19] = [
+    "format",
+    "text",
+    "json",
+    "ndjson",
+    "flat_json",
+    "yaml",
+    "toml",
+    "html",
+    "mcnp",
+    "serpent",
+    "tripoli",
+    "fluka",
+    "origen",
+    "geant4",
+    "npz",
+    "export_csv",
+    "tsv",
+    "csv",
+    "template",
+];
+
+/// Default options from a `decaydata.toml` config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    rad: Option<Vec<String>>,
+    sort: Option<String>,
+    output: Option<String>,
+    format: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Read the config file given by --config, or the first one found
+    fn load(cli: &Cli) -> Result<Option<(PathBuf, Self)>> {
+        let path = match &cli.config {
+            Some(path) if !path.is_file() => {
+                bail!("Config file \"{}\" does not exist", path.display())
+            }
+            Some(path) => path.clone(),
+            None => match Self::search() {
+                Some(path) => path,
+                None => return Ok(None),
+            },
+        };
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read config file \"{}\"", path.display()))?;
+        let config = toml::from_str(&text)
+            .with_context(|| format!("Invalid config file \"{}\"", path.display()))?;
+        Ok(Some((path, config)))
+    }
+
+    /// Look in the working directory, then the user config directory
+    fn search() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+
+        [Some(PathBuf::from(CONFIG_NAME)), config_home.map(|d| d.join(CONFIG_NAME))]
+            .into_iter()
+            .flatten()
+            .find(|path| path.is_file())
+    }
+
+    /// Set any option that was not given explicitly on the command line
+    ///
+    /// The config formats are only used if no output format was chosen at all,
+    /// rather than combined with those on the command line.
+    fn apply(self, cli: &mut Cli, matches: &ArgMatches) -> Result<()> {
+        if let Some(rad) = self.rad.filter(|_| !is_explicit(matches, "rad")) {
+            cli.rad = rad
+                .iter()
+                .map(|r| parse_value::<CliRadType>("rad", r))
+                .collect::<Result<_>>()?;
+        }
+
+        if let Some(sort) = self.sort.filter(|_| !is_explicit(matches, "sort")) {
+            cli.sort = parse_value::<Property>("sort", &sort)?;
+        }
+
+        if let Some(output) = self.output.filter(|_| !is_explicit(matches, "output")) {
+            cli.output = output;
+        }
+
+        let any_output = OUTPUT_ARGS.iter().any(|id| is_explicit(matches, id));
+        if let Some(format) = self.format.filter(|_| !any_output) {
+            cli.format = format
+                .iter()
+                .map(|f| parse_value::<OutputFormat>("format", f))
+                .collect::<Result<_>>()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the command line, filling in defaults from any config file
+pub fn parse() -> Result<Cli> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    finish_parsing(cli, &matches)
+}
+
+/// Parse a list of arguments as if given on the command line, e.g. for --batch
+pub fn parse_from(args: &[String]) -> Result<Cli> {
+    let matches = Cli::command().try_get_matches_from(args)?;
+    let cli = Cli::from_arg_matches(&matches)?;
+    finish_parsing(cli, &matches)
+}
+
+/// Apply the config file and unit conversions to freshly parsed arguments
+fn finish_parsing(mut cli: Cli, matches: &ArgMatches) -> Result<Cli> {
+    if let Some((path, config)) = Config::load(&cli)? {
+        config.apply(&mut cli, matches)?;
+        cli.config = Some(path);
+    }
+
+    apply_energy_unit(&mut cli, matches);
+    cli.rad = CliRadType::expand(&cli.rad);
+    validate(&cli)?;
+    Ok(cli)
+}
+
+/// Check option values that clap alone cannot, before anything is fetched
+fn validate(cli: &Cli) -> Result<()> {
+    if cli.bin_width.is_some_and(|w| !w.is_finite() || w <= 0.0) {
+        bail!("--bin-width must be a positive energy");
+    }
+
+    // even lines only 1 MeV apart would need more than the maximum bins
+    let min_width = 1000.0 / MAX_BINS as f32;
+    if cli.bin_width.is_some_and(|w| w < min_width) {
+        bail!("--bin-width must be at least {min_width} keV, at most {MAX_BINS} bins are allowed");
+    }
+
+    if cli.mcnp_emax.is_some_and(|e| !e.is_finite() || e <= 0.0) {
+        bail!("--mcnp-emax must be a positive energy");
+    }
+
+    if cli.mcnp_indent < 5 {
+        bail!("--mcnp-indent must be at least 5, or MCNP reads continuations as new cards");
+    }
+
+    if cli.mcnp_width <= cli.mcnp_indent {
+        bail!("--mcnp-width must be greater than --mcnp-indent");
+    }
+
+    Ok(())
+}
+
+/// Convert every energy given on the command line to keV
+///
+/// Defaults are always in keV, so only explicit values are converted.
+fn apply_energy_unit(cli: &mut Cli, matches: &ArgMatches) {
+    let unit = cli.energy_unit;
+    cli.bin_width = cli.bin_width.map(|w| unit.to_kev(w));
+    cli.merge_duplicates = cli.merge_duplicates.map(|tol| unit.to_kev(tol));
+
+    if is_explicit(matches, "diff_tolerance") {
+        cli.diff_tolerance = unit.to_kev(cli.diff_tolerance);
+    }
+
+    if is_explicit(matches, "shell_tolerance") {
+        cli.shell_tolerance = unit.to_kev(cli.shell_tolerance);
+    }
+}
+
+/// Check if an argument was given on the command line rather than defaulted
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// Parse a config value the same way clap would on the command line
+fn parse_value<T: ValueEnum>(key: &str, value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|_| anyhow!("Invalid {key} \"{value}\" in config file"))
+}
+
 /// Customise the colour styles for clap v4
 fn custom_style() -> Styles {
     Styles::styled()
@@ -192,15 +1419,75 @@ fn custom_style() -> Styles {
 }
 
 /// Sets up logging at runtime to allow for multiple verbosity levels
+///
+/// Logs go to stderr unless --quiet or --log-file-only are used, and to the
+/// --log-file if one is given. Only the stderr sink is ever coloured and the
+/// file sink is always timestamped.
 pub fn init_logging(cli: &Cli) -> Result<()> {
+    let level = match cli.verbose {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+
+    let mut logger = fern::Dispatch::new()
+        .level(level)
+        .filter(|metadata| metadata.target().starts_with("ddata"));
+
+    if !cli.quiet && !cli.log_file_only {
+        logger = logger.chain(stderr_sink(cli));
+    }
+
+    if let Some(path) = &cli.log_file {
+        logger = logger.chain(file_sink(path)?);
+    }
+
+    Ok(logger.apply()?)
+}
+
+/// Plain log messages on stderr, coloured by level if a terminal
+fn stderr_sink(cli: &Cli) -> fern::Dispatch {
     let show_level = cli.verbose > 0;
+    let colour = !cli.colour_disabled() && std::io::stderr().is_terminal();
+
+    fern::Dispatch::new()
+        .format(move |out, message, record| {
+            let line = match show_level {
+                true => format!("{} - {message}", record.level()),
+                false => message.to_string(),
+            };
+
+            match colour {
+                true => out.finish(format_args!("{}", line.color(level_colour(record.level())))),
+                false => out.finish(format_args!("{line}")),
+            }
+        })
+        .chain(std::io::stderr())
+}
+
+/// Timestamped log messages appended to a file
+fn file_sink(path: &Path) -> Result<fern::Dispatch> {
+    let file = fern::log_file(path)
+        .with_context(|| format!("Unable to open log file \"{}\"", path.display()))?;
+
+    Ok(fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "{} {:<5} {message}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level()
+            ))
+        })
+        .chain(file))
+}
 
-    Ok(stderrlog::new()
-        .module("ddata")
-        .quiet(cli.quiet)
-        .verbosity(cli.verbose as usize + 2)
-        .show_level(show_level)
-        .color(stderrlog::ColorChoice::Auto)
-        .timestamp(stderrlog::Timestamp::Off)
-        .init()?)
+/// Colour of each log level on stderr
+fn level_colour(level: Level) -> Color {
+    match level {
+        Level::Error => Color::Red,
+        Level::Warn => Color::Magenta,
+        Level::Info => Color::Yellow,
+        Level::Debug => Color::Cyan,
+        Level::Trace => Color::Blue,
+    }
 }
@@ -1,6 +1,8 @@
 // internal
-use crate::create_file_with_fallback;
+use crate::{create_file_with_fallback, has_content, FileOptions};
+use crate::net;
 use crate::nuclide::NuclideData;
+use crate::wrappers::CliRadType;
 
 // standard lib
 use std::io::Write;
@@ -8,23 +10,141 @@ use std::path::Path;
 
 // other
 use anyhow::Result;
+use log::{error, warn};
 
 // neutronics toolbox
-use ntools::iaea::{self, RadType};
+use ntools::iaea::RadType;
 use ntools::utils::f;
 
+/// File extension for the raw IAEA CSV
+pub const EXTENSION: &str = "csv";
+
+/// File extension for the processed records CSV
+pub const PROCESSED_EXTENSION: &str = "records.csv";
+
+/// File extension for the processed records TSV
+pub const TSV_EXTENSION: &str = "tsv";
+
+/// UTF-8 byte order mark, used by Excel to detect the encoding
+const BOM: &str = "\u{feff}";
+
 /// Writes the completely unedited data to a CSV direct from IAEA
-pub fn write(nuclides: &[NuclideData], rad_type: RadType, path: &Path) -> Result<()> {
-    let mut f = create_file_with_fallback(path, "csv", "decay_data.csv")?;
+pub fn write(
+    nuclides: &[NuclideData],
+    rad_types: &[CliRadType],
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.csv", file)?;
 
-    let csv_records = fetch_csv_records(nuclides, rad_type);
-    f.write_all(csv_records.as_bytes())?;
-    Ok(())
+    let mut failures = 0;
+    for (_, rad_type) in CliRadType::iaea_types(rad_types) {
+        let (csv_records, failed) = fetch_csv_records(nuclides, rad_type);
+        f.write_all(csv_records.as_bytes())?;
+        failures += failed;
+    }
+
+    if failures > 0 {
+        error!("{failures} raw CSV request(s) failed, marked in the output as \"Request failed\"");
+    }
+    f.finish()
 }
 
-/// Make source distribution cards for every nuclide
-fn fetch_csv_records(nuclides: &[NuclideData], rad_type: RadType) -> String {
+/// Writes the processed decay data records to a clean CSV
+///
+/// Unlike [write], this uses the filtered and sorted records and so works
+/// entirely offline. Missing values are left as empty cells.
+///
+/// The `delimiter` replaces the comma if given, and `bom` prepends a UTF-8
+/// byte order mark to new files for Excel.
+pub fn write_processed(
+    nuclides: &[NuclideData],
+    path: &Path,
+    file: FileOptions,
+    delimiter: Option<char>,
+    bom: bool,
+) -> Result<()> {
+    // only need the header once if appending to an existing file
+    let header = !(file.append && has_content(path, PROCESSED_EXTENSION, file));
+
+    let mut f = create_file_with_fallback(path, PROCESSED_EXTENSION, "decay_records.csv", file)?;
+    if bom && header {
+        f.write_all(BOM.as_bytes())?;
+    }
+    let records = processed_records(nuclides, header, delimiter.unwrap_or(','));
+    f.write_all(records.as_bytes())?;
+    f.finish()
+}
+
+/// Writes the processed decay data records as tab-separated values
+///
+/// Identical to [write_processed] other than the separator, which is easier to
+/// paste into spreadsheets. The tab is always used, but `bom` still applies.
+pub fn write_tsv(
+    nuclides: &[NuclideData],
+    path: &Path,
+    file: FileOptions,
+    bom: bool,
+) -> Result<()> {
+    let mut f = create_file_with_fallback(path, TSV_EXTENSION, "decay_records.tsv", file)?;
+    if bom {
+        f.write_all(BOM.as_bytes())?;
+    }
+    f.write_all(processed_records(nuclides, true, '\t').as_bytes())?;
+    f.finish()
+}
+
+/// Make a row for every processed record of every nuclide
+fn processed_records(nuclides: &[NuclideData], header: bool, separator: char) -> String {
+    let units = nuclides.first().map(|n| n.units).unwrap_or_default();
+    let energy = nuclides.first().map(|n| n.energy_unit).unwrap_or_default();
+    let separator = separator.to_string();
+
     let mut csv = String::new();
+    if header {
+        for nuclide in nuclides.iter().filter(|n| n.with_metadata) {
+            csv += &f!("# {}: {}\n", nuclide.name, nuclide.metadata());
+        }
+
+        csv += &[
+            "nuclide".to_string(),
+            f!("energy_{energy}"),
+            f!("intensity_{units}"),
+            f!("parent_energy_{energy}"),
+        ]
+        .join(&separator);
+        csv += "\n";
+    }
+
+    for nuclide in nuclides {
+        for record in &nuclide.records {
+            csv += &[
+                nuclide.name.clone(),
+                cell(nuclide.in_energy_unit(record.energy)),
+                cell(nuclide.in_units(record.intensity)),
+                cell(nuclide.in_energy_unit(record.p_energy)),
+            ]
+            .join(&separator);
+            csv += "\n";
+        }
+    }
+
+    csv
+}
+
+/// Format an optional value as a CSV cell
+fn cell(value: Option<f32>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Fetch the raw CSV of every nuclide, with the number of failed requests
+///
+/// A failed request is logged as an error and marked in the output, while a
+/// successful response with no records is only a warning. Either way the
+/// other nuclides are still fetched.
+fn fetch_csv_records(nuclides: &[NuclideData], rad_type: RadType) -> (String, usize) {
+    let mut csv = String::new();
+    let mut failures = 0;
 
     // can only get all records, so will need to dedup excied states and just
     // return everything
@@ -36,12 +156,24 @@ fn fetch_csv_records(nuclides: &[NuclideData], rad_type: RadType) -> String {
 
     for nuclide in &requests {
         csv += &f!("\nIAEA {nuclide} CSV records for {:?} decay\n", rad_type);
-        csv += &iaea::fetch_csv(nuclide, rad_type).unwrap_or(f!(
-            "\nNo CSV data found for {:?} records of {}",
-            rad_type,
-            nuclide
-        ))
+        match net::fetch_csv(nuclide, rad_type) {
+            Ok(records) if has_records(&records) => csv += &records,
+            Ok(_) => {
+                warn!("No {rad_type:?} CSV records exist for {nuclide}");
+                csv += &f!("\nNo CSV data found for {:?} records of {}", rad_type, nuclide);
+            }
+            Err(e) => {
+                error!("Request for {rad_type:?} CSV of {nuclide} failed: {e}");
+                csv += &f!("\nRequest failed for {:?} records of {}", rad_type, nuclide);
+                failures += 1;
+            }
+        }
     }
 
-    csv
+    (csv, failures)
+}
+
+/// Check a CSV response has anything beyond the column titles
+fn has_records(csv: &str) -> bool {
+    csv.lines().filter(|l| !l.trim().is_empty()).count() > 1
 }
@@ -0,0 +1,71 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use anyhow::Result;
+
+const KEV_TO_GEV: f32 = 1.0e-06;
+
+/// File extension for FLUKA spectrum files
+pub const EXTENSION: &str = "spc";
+
+/// Writes the FLUKA source spectra to a file at the specified path.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "fluka.spc", file)?;
+    let spectra = generate_spectra(nuclides);
+    f.write_all(spectra.as_bytes())?;
+    f.finish()
+}
+
+/// Make spectra for every nuclide
+fn generate_spectra(nuclides: &[NuclideData]) -> String {
+    nuclides
+        .iter()
+        .map(nuclide_spectrum)
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+/// Make a two column energy-weight spectrum for a nuclide
+///
+/// Energies are in GeV as expected by FLUKA, and the weights are normalised to
+/// sum to 1 for sampling in a user SOURCE routine.
+fn nuclide_spectrum(nuclide: &NuclideData) -> String {
+    let filtered_records = nuclide.valid_lines();
+
+    // only the lines written count towards the weights
+    let norm = filtered_records
+        .iter()
+        .fold(0.0, |acc, r| acc + r.intensity.unwrap() * 1e-2);
+    if filtered_records.is_empty() || norm <= 0.0 {
+        return f!("# {} records contained no valid decay data\n", nuclide.name);
+    }
+
+    // Comment header with nuclide name and normalization factor
+    let mut spectrum = f!(
+        "# {} decay data, norm = {} particles/decay\n# {:<13} {:<13}\n",
+        nuclide.name,
+        nuclide.norm().sci(5, 2),
+        "energy [GeV]",
+        "weight"
+    );
+
+    for record in filtered_records {
+        spectrum += &f!(
+            "  {:<13} {:<13}\n",
+            (record.energy.unwrap() * KEV_TO_GEV).sci(5, 2),
+            (record.intensity.unwrap() * 1e-2 / norm).sci(5, 2)
+        );
+    }
+
+    spectrum += "\n";
+    spectrum
+}
@@ -0,0 +1,131 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::mcnp::merge_equal_energies;
+use crate::nuclide::{NuclideData, TaggedRecord};
+use crate::wrappers::CliRadType;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use anyhow::Result;
+
+const KEV_TO_MEV: f32 = 1.0e-03;
+
+/// Width of the histogram bin below each line, relative to its energy
+const LINE_WIDTH: f32 = 1.0e-03;
+
+/// File extension for Geant4 macros
+pub const EXTENSION: &str = "mac";
+
+/// Writes the Geant4 GPS macro commands to a file at the specified path.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "geant4.mac", file)?;
+    let macro_text = generate_gps_macro(nuclides);
+    f.write_all(macro_text.as_bytes())?;
+    f.finish()
+}
+
+/// Make GPS source commands for every nuclide
+fn generate_gps_macro(nuclides: &[NuclideData]) -> String {
+    let mut first = true;
+    nuclides
+        .iter()
+        .map(|n| nuclide_source(n, &mut first))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+/// Make the GPS energy spectrum commands for a nuclide
+///
+/// Geant4 needs a particle per source, so a separate block is written for
+/// each radiation type. GPS always has one source to begin with, so only the
+/// blocks after the `first` of the macro add a new one. Every source has the
+/// sum of its line intensities as its relative intensity.
+///
+/// Each point of a GPS user histogram is the upper edge of a bin, and the
+/// weight of the first point is ignored. Every line is therefore the upper
+/// edge of a narrow bin, preceded by a zero weight point at its lower edge,
+/// so that the spectrum stays as close to discrete lines as possible.
+fn nuclide_source(nuclide: &NuclideData, first: &mut bool) -> String {
+    let filtered_records = nuclide.valid_lines();
+
+    if filtered_records.is_empty() {
+        return f!("# {} records contained no valid decay data\n", nuclide.name);
+    }
+
+    let mut commands = String::new();
+    for rad_type in nuclide.rad_types() {
+        let Some(particle) = particle(rad_type) else {
+            continue;
+        };
+
+        let mut records = filtered_records
+            .iter()
+            .filter(|r| r.rad_type == rad_type)
+            .collect::<Vec<&&TaggedRecord>>();
+
+        if records.is_empty() {
+            continue;
+        }
+
+        // Histogram points must be in strictly ascending energy
+        records.sort_by(|a, b| a.energy.partial_cmp(&b.energy).unwrap());
+        let lines = merge_equal_energies(
+            records
+                .iter()
+                .map(|r| (r.energy.unwrap() * KEV_TO_MEV, r.intensity.unwrap() * 1e-2))
+                .collect(),
+        );
+
+        let norm = records
+            .iter()
+            .fold(0.0, |acc, r| acc + r.intensity.unwrap() as f64 * 1e-2);
+
+        // Comment header with nuclide name and normalization factor
+        commands += &f!(
+            "# {} {} decay data, norm = {} particles/decay\n",
+            nuclide.name,
+            rad_type,
+            norm.sci(5, 2)
+        );
+
+        match *first {
+            true => commands += &f!("/gps/source/intensity {}\n", norm.sci(5, 2)),
+            false => commands += &f!("/gps/source/add {}\n", norm.sci(5, 2)),
+        }
+        *first = false;
+
+        commands += &f!("/gps/particle {particle}\n");
+        commands += "/gps/ene/type User\n";
+        commands += "/gps/hist/type energy\n";
+
+        // the bin below each line never reaches back past the previous line
+        let mut previous = 0.0;
+        for (energy, weight) in lines {
+            let lower = energy - (LINE_WIDTH * energy).min(0.5 * (energy - previous));
+            commands += &f!("/gps/hist/point {} 0\n", lower.sci(5, 2));
+            commands += &f!("/gps/hist/point {} {}\n", energy.sci(5, 2), weight.sci(5, 2));
+            previous = energy;
+        }
+
+        commands += "\n";
+    }
+
+    commands
+}
+
+/// Geant4 particle name for the radiation type, if a single type
+fn particle(rad_type: CliRadType) -> Option<&'static str> {
+    match rad_type {
+        CliRadType::Alpha => Some("alpha"),
+        CliRadType::BetaPlus => Some("e+"),
+        CliRadType::BetaMinus | CliRadType::Electron => Some("e-"),
+        CliRadType::Gamma | CliRadType::Xray => Some("gamma"),
+        CliRadType::All => None,
+    }
+}
@@ -0,0 +1,98 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, OptionExt, ValueExt};
+
+// other
+use anyhow::Result;
+
+/// File extension for the HTML report
+pub const EXTENSION: &str = "html";
+
+/// Minimal embedded styling so the report is self-contained
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; min-width: 40em; }
+caption { text-align: left; font-weight: bold; padding: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: right; }
+th { background: #4a6fa5; color: #fff; }
+tr:nth-child(even) { background: #f2f5fa; }
+td.none { text-align: center; font-style: italic; color: #888; }
+";
+
+/// Writes a self-contained HTML report to a file at the specified path.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.html", file)?;
+    f.write_all(generate_report(nuclides).as_bytes())?;
+    f.finish()
+}
+
+/// Make the full report with one table per nuclide
+fn generate_report(nuclides: &[NuclideData]) -> String {
+    let mut html = f!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Decay data</title>\n<style>{STYLE}</style>\n</head>\n<body>\n"
+    );
+
+    for nuclide in nuclides {
+        html += &nuclide_table(nuclide);
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+/// Make a single table of records for a nuclide
+fn nuclide_table(nuclide: &NuclideData) -> String {
+    let mut table = f!(
+        "<table>\n<caption>{} decay data, norm = {} particles/decay</caption>\n",
+        escape(&nuclide.name),
+        nuclide.norm().sci(5, 2)
+    );
+
+    table += &f!(
+        "<tr><th>Parent</th><th>Mode</th><th>Daughter</th><th>Radiation</th>\
+         <th>Energy [{}]</th><th>Intensity [{}]</th></tr>\n",
+        nuclide.energy_unit,
+        nuclide.units.symbol()
+    );
+
+    if nuclide.records.is_empty() {
+        table += "<tr><td class=\"none\" colspan=\"6\">No data</td></tr>\n";
+    }
+
+    for record in &nuclide.records {
+        table += &f!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(&record.parent_name()),
+            escape(&record.decay_mode.display()),
+            escape(&record.daughter_name()),
+            record.rad_type,
+            cell(nuclide.in_energy_unit(record.energy)),
+            cell(nuclide.in_units(record.intensity))
+        );
+    }
+
+    table += "</table>\n";
+    table
+}
+
+/// Format an optional value as a table cell
+fn cell(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |v| v.to_string())
+}
+
+/// Escape text for safe inclusion in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -1,23 +1,226 @@
 // internal
-use crate::create_file_with_fallback;
+use crate::{create_file_with_fallback, FileOptions};
 use crate::nuclide::NuclideData;
+use crate::wrappers::{CliRadType, EnergyUnit, IntensityUnits};
 
 // standard lib
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 
 // other
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// File extension for JSON output
+pub const EXTENSION: &str = "json";
+
+/// File extension for newline-delimited JSON output
+pub const NDJSON_EXTENSION: &str = "ndjson";
+
+/// File extension for the flat JSON output
+pub const FLAT_EXTENSION: &str = "flat.json";
+
+/// Version of the JSON output structure, incremented on any breaking change
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Versioned wrapper around the nuclide data for JSON output
+#[derive(Debug, Serialize)]
+pub struct JsonEnvelope<'a> {
+    /// Version of the output structure
+    pub schema_version: u32,
+    /// Radiation type(s) requested, comma separated
+    pub rad_type: String,
+    /// Time of generation as an ISO 8601 UTC timestamp
+    pub generated: String,
+    /// Units of every intensity, from --intensity-units
+    pub intensity_units: String,
+    /// Units of every energy, from --out-energy-unit
+    pub energy_unit: String,
+    /// Decay data for every nuclide
+    pub nuclides: &'a [NuclideData],
+}
+
+impl<'a> JsonEnvelope<'a> {
+    /// Wrap the nuclide data, using the current time for the timestamp
+    pub fn new(nuclides: &'a [NuclideData], rad_types: &[CliRadType]) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            rad_type: rad_types
+                .iter()
+                .map(|r| r.name())
+                .collect::<Vec<&str>>()
+                .join(","),
+            generated: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            intensity_units: nuclides
+                .first()
+                .map(|n| n.units)
+                .unwrap_or_default()
+                .to_string(),
+            energy_unit: nuclides
+                .first()
+                .map(|n| n.energy_unit)
+                .unwrap_or_default()
+                .to_string(),
+            nuclides,
+        }
+    }
+}
+
+/// A single record for the flat JSON output
+#[derive(Debug, Serialize)]
+struct FlatRecord<'a> {
+    nuclide: &'a str,
+    radiation: &'a str,
+    energy: Option<f32>,
+    intensity: Option<f32>,
+    parent_energy: Option<f32>,
+}
+
+/// Versioned envelope read back from a JSON file
+///
+/// The bare array of older outputs is read into the same structure without
+/// any units, see [read].
+#[derive(Deserialize)]
+struct JsonInput {
+    nuclides: Vec<NuclideData>,
+    #[serde(default)]
+    intensity_units: Option<String>,
+    #[serde(default)]
+    energy_unit: Option<String>,
+}
 
 /// Writes the nuclide data to a JSON file at the specified path.
 ///
 /// # Arguments
 ///
 /// * `path` - The path where the JSON data should be written.
+/// * `rad_types` - The radiation types requested, recorded in the envelope.
+/// * `file` - How the output file is opened, e.g. compressed.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write(
+    nuclides: &[NuclideData],
+    rad_types: &[CliRadType],
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.json", file)?;
+    let envelope = JsonEnvelope::new(nuclides, rad_types);
+    serde_json::to_writer_pretty(&mut f, &envelope).context("Unable to serialise to JSON")?;
+    f.finish()
+}
+
+/// Writes the nuclide data as newline-delimited JSON, one nuclide per line.
+///
+/// # Arguments
+///
+/// * `path` - The path where the NDJSON data should be written.
+/// * `file` - How the output file is opened, e.g. compressed.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write_ndjson(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let f = create_file_with_fallback(path, NDJSON_EXTENSION, "decay_data.ndjson", file)?;
+    let mut writer = BufWriter::new(f);
+    for nuclide in nuclides {
+        let line = serde_json::to_string(nuclide).context("Unable to serialise to JSON")?;
+        writeln!(writer, "{line}")?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.finish()
+}
+
+/// Writes every record as a flat array of JSON objects, one per record.
+///
+/// Each object has the nuclide name, radiation type, energy, intensity, and
+/// parent energy, so there are no parallel arrays to keep aligned. Values use
+/// the same units as the nested output.
+///
+/// # Arguments
+///
+/// * `path` - The path where the flat JSON data should be written.
+/// * `file` - How the output file is opened, e.g. compressed.
 ///
 /// # Returns
 ///
 /// A `Result` indicating success or failure.
-pub fn write(nuclides: &[NuclideData], path: &Path) -> Result<()> {
-    let f = create_file_with_fallback(path, "json", "decay_data.json")?;
-    serde_json::to_writer_pretty(f, &nuclides).context("Unable to serialise to JSON")
+pub fn write_flat(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, FLAT_EXTENSION, "decay_data.flat.json", file)?;
+
+    let records = nuclides
+        .iter()
+        .flat_map(|n| {
+            n.records.iter().map(move |r| FlatRecord {
+                nuclide: &n.name,
+                radiation: r.rad_type.name(),
+                energy: n.in_energy_unit(r.energy),
+                intensity: n.in_units(r.intensity),
+                parent_energy: n.in_energy_unit(r.p_energy),
+            })
+        })
+        .collect::<Vec<FlatRecord>>();
+
+    serde_json::to_writer_pretty(&mut f, &records).context("Unable to serialise to JSON")?;
+    f.finish()
+}
+
+/// Reads nuclide data back from a JSON file previously written by [write].
+///
+/// Both the versioned envelope and the bare nuclide array of older versions
+/// are accepted. Intensities and energies are converted back to percent and
+/// keV using the units recorded in the envelope, and older files are assumed
+/// to already be in percent and keV.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file to read.
+///
+/// # Returns
+///
+/// A `Result` containing the reconstructed nuclide data.
+pub fn read(path: &Path) -> Result<Vec<NuclideData>> {
+    let f = File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_reader(BufReader::new(f))
+        .with_context(|| format!("Invalid JSON in {}", path.display()))?;
+
+    // decide on the structure first, so that any error is for the real problem
+    let input = match value.is_array() {
+        true => JsonInput {
+            nuclides: serde_json::from_value(value)
+                .context("Unable to deserialise JSON decay data")?,
+            intensity_units: None,
+            energy_unit: None,
+        },
+        false => serde_json::from_value(value).context("Unable to deserialise JSON decay data")?,
+    };
+
+    let JsonInput {
+        mut nuclides,
+        intensity_units: units,
+        energy_unit,
+    } = input;
+
+    if let Some(units) = units {
+        let units = IntensityUnits::from_str(&units, true)
+            .map_err(|_| anyhow!("Unknown intensity units \"{units}\" in {}", path.display()))?;
+        for record in nuclides.iter_mut().flat_map(|n| n.records.iter_mut()) {
+            record.intensity = record.intensity.map(|i| i / units.scale());
+        }
+    }
+
+    if let Some(unit) = energy_unit {
+        let unit = EnergyUnit::from_str(&unit, true)
+            .map_err(|_| anyhow!("Unknown energy unit \"{unit}\" in {}", path.display()))?;
+        for record in nuclides.iter_mut().flat_map(|n| n.records.iter_mut()) {
+            record.energy = record.energy.map(|e| unit.to_kev(e));
+            record.p_energy = record.p_energy.map(|e| unit.to_kev(e));
+        }
+    }
+
+    Ok(nuclides)
 }
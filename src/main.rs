@@ -2,84 +2,923 @@
 #![doc(hidden)]
 
 // crate modules
+mod abundance;
+mod aliases;
+mod batch;
 mod cli;
 mod csv;
+mod fluka;
+mod geant4;
+mod html;
 mod json;
 mod mcnp;
+mod net;
+mod npz;
 mod nuclide;
+mod origen;
+mod plot;
+mod serpent;
 mod table;
+mod template;
+mod timing;
+mod toml_out;
+mod tripoli;
 mod wrappers;
+mod yaml;
 
 // Standard lib
-use std::fs::{self, File};
-use std::path::Path;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 // external crates
-use anyhow::{Context, Ok, Result};
-use clap::Parser;
-use log::{debug, error, warn};
+use anyhow::{bail, Context, Ok, Result};
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, error, info, warn};
 
-fn main() -> Result<()> {
+// neutronics toolbox
+use ntools::iaea::{self, IsomerState};
+
+fn main() -> ExitCode {
+    if let Err(e) = try_main() {
+        eprintln!("Error: {e:?}");
+        return ExitCode::from(e.downcast_ref::<Failure>().map_or(1, Failure::code));
+    }
+    ExitCode::SUCCESS
+}
+
+/// Failures with their own exit code, so that scripts may tell them apart
+///
+/// Any other error exits with 1, and invalid arguments exit with 2 as for any
+/// other clap application. The codes here start from 3 so that each one only
+/// ever means one thing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Failure {
+    /// None of the inputs matched a nuclide with decay data
+    NoNuclides,
+    /// Nuclides were found, but none have any records left
+    NoRecords,
+    /// Every output file failed to write
+    WritesFailed,
+}
+
+impl Failure {
+    /// Process exit code for the failure
+    fn code(&self) -> u8 {
+        match self {
+            Failure::NoNuclides => 3,
+            Failure::NoRecords => 4,
+            Failure::WritesFailed => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Failure::NoNuclides => write!(f, "No nuclides to query"),
+            Failure::NoRecords => write!(f, "No nuclides have relevant decay data records"),
+            Failure::WritesFailed => write!(f, "Unable to write any of the output files"),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn try_main() -> Result<()> {
     // set up the command line interface and logging
-    let cli = cli::Cli::parse();
+    let cli = cli::parse()?;
     cli::init_logging(&cli)?;
+    net::init(&cli);
+
+    match &cli.batch {
+        Some(path) => run_batch(path),
+        None => run(cli),
+    }
+}
+
+/// Run every job in a batch file, reporting all failures at the end
+///
+/// Logging and the retry policy are set up once from the main command line,
+/// so these options are ignored in the jobs themselves.
+fn run_batch(path: &Path) -> Result<()> {
+    let jobs = batch::load(path)?;
+    info!("Running {} jobs from {}", jobs.len(), path.display());
+
+    let mut failures = Vec::new();
+    for (i, job) in jobs.iter().enumerate() {
+        let label = job.label(i + 1);
+        info!("Batch job {}/{}: {label}", i + 1, jobs.len());
+
+        let result = cli::parse_from(&job.args(i + 1)).and_then(run);
+        if let Err(e) = result {
+            error!("Batch job {label} failed: {e:#}");
+            failures.push(label);
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} batch jobs failed: {}",
+            failures.len(),
+            jobs.len(),
+            failures.join(", ")
+        )
+    }
+    Ok(())
+}
+
+/// Run a single query from start to finish
+fn run(mut cli: cli::Cli) -> Result<()> {
+    cli.apply_formats();
 
-    debug!("Parsing command line nuclides");
-    let mut nuclides = nuclide::parse_nuclides(&cli)?;
+    // explicit format flags always win over the --output extension
+    if enabled_outputs(&cli).is_empty() {
+        infer_format(&mut cli);
+    }
+    let mut timings = timing::Timings::new(cli.timing);
+
+    if let Some(path) = &cli.config {
+        debug!("Default options from config file {}", path.display());
+    }
+
+    if cli.data_info {
+        debug!("Reporting decay data provenance");
+        return data_info(&cli);
+    }
+
+    if cli.list_available {
+        debug!("Listing available nuclides");
+        let names = nuclide::available_names(&cli)?;
+        match cli.json {
+            true => println!("{}", serde_json::to_string_pretty(&names)?),
+            false => names.iter().for_each(|n| println!("{n}")),
+        }
+        return Ok(());
+    }
+
+    if cli.interactive && cli.from_json.is_none() {
+        match io::stdin().is_terminal() && io::stdout().is_terminal() {
+            true => cli.nuclides = pick_nuclides(&cli)?,
+            false => warn!("Ignoring --interactive, not running in a terminal"),
+        }
+    }
+
+    // check the mixture weights before anything is fetched
+    let weights = nuclide::parse_weights(&cli.weights)?;
+
+    let mut nuclides = match &cli.from_json {
+        Some(file) => {
+            debug!("Reading decay data from {file}");
+            timings.time("Reading JSON", || json::read(Path::new(file)))?
+        }
+        None => {
+            debug!("Parsing command line nuclides");
+            // avoid accidentally hammering the IAEA API with a large request
+            let limit = (cli.fetch && !cli.yes).then_some(cli.max_nuclides);
+            let mut nuclides =
+                timings.time("Parsing nuclides", || nuclide::parse_nuclides(&cli, limit))?;
+            nuclide::exclude_nuclides(&mut nuclides, &cli.exclude);
+            if nuclides.is_empty() {
+                return Err(Failure::NoNuclides.into());
+            }
+
+            // fill with records for the relevant decay type
+            debug!("Retrieving decay data");
+            let progress = fetch_progress(&cli, nuclides.len())?;
+            timings.time("Retrieving records", || {
+                for n in nuclides.iter_mut() {
+                    progress.set_message(n.name.clone());
+                    n.find_records(
+                        &cli.rad,
+                        cli.fetch,
+                        cli.explain,
+                        cli.drop_unobserved,
+                        cli.json_raw,
+                    );
+                    progress.inc(1);
+                }
+            });
+            progress.finish_and_clear();
+
+            if cli.strict {
+                let failures = nuclides
+                    .iter()
+                    .filter(|n| n.records.is_empty())
+                    .map(|n| n.name.as_str())
+                    .collect::<Vec<&str>>();
+
+                if !failures.is_empty() {
+                    bail!("No decay data found for: {}", failures.join(", "))
+                }
+            }
+
+            nuclides
+        }
+    };
 
-    // fill with records for the relevant decay type
-    debug!("Retrieving decay data");
     for n in nuclides.iter_mut() {
-        n.find_records(cli.rad.into(), cli.fetch);
+        let duplicates = n.duplicate_energies();
+        if !duplicates.is_empty() {
+            let message = format!(
+                "{} lists the same energy more than once: {}",
+                n.name,
+                duplicates
+                    .iter()
+                    .map(|e| format!("{e} keV"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            match cli.warn_duplicates {
+                true => warn!("{message}"),
+                false => debug!("{message}"),
+            }
+        }
+
+        if let Some(tolerance) = cli.merge_duplicates {
+            n.merge_close(tolerance);
+        }
         n.sort_records(&cli.sort);
+        if cli.reverse {
+            n.reverse_records();
+        }
+        n.activity = cli.activity;
+        n.units = cli.intensity_units;
+        n.energy_unit = cli.out_energy_unit;
+        n.show_source = cli.show_source;
+        n.with_metadata = cli.with_metadata;
+    }
+
+    // restrict to either ground or excited states if requested
+    if cli.only_ground || cli.only_isomers {
+        nuclides.retain(|n| {
+            let excited = matches!(n.nuclide.state, IsomerState::Excited(_));
+            match cli.only_isomers {
+                true => excited,
+                false => !excited,
+            }
+        });
     }
 
     // filter out anything with no remaining records
     nuclides.retain(|n| !n.records.is_empty());
 
+    if let Some(tolerance) = cli.norm_tolerance {
+        debug!("Checking total intensities");
+        for warning in nuclides.iter().filter_map(|n| n.norm_warning(tolerance / 100.0)) {
+            warn!("{warning}");
+        }
+    }
+
+    if cli.renormalize {
+        debug!("Renormalising intensities to 100%");
+        for n in nuclides.iter_mut() {
+            n.renormalize();
+        }
+    }
+
+    if cli.relative {
+        debug!("Normalising intensities to the strongest line");
+        for n in nuclides.iter_mut() {
+            n.normalize_to_max();
+        }
+    }
+
+    if cli.natural {
+        debug!("Weighting intensities by natural abundance");
+        nuclides.retain_mut(|n| match abundance::fraction(&n.nuclide) {
+            Some(fraction) => {
+                n.scale_intensities(fraction);
+                true
+            }
+            None => {
+                debug!("Removing {}, not naturally occurring", n.name);
+                false
+            }
+        });
+    }
+
+    if cli.sum {
+        debug!("Summing all nuclides into a single mixture");
+        nuclides = nuclide::combine(&nuclides, &weights).into_iter().collect();
+        for n in nuclides.iter_mut() {
+            n.sort_records(&cli.sort);
+            if cli.reverse {
+                n.reverse_records();
+            }
+        }
+    }
+
     // if none of them had decay data, then sources will be empty
     if nuclides.is_empty() {
-        error!("No nuclides have relevant decay data records");
+        return Err(Failure::NoRecords.into());
+    }
+
+    debug!("Ordering nuclides by {}", cli.nuclide_order);
+    nuclide::sort_nuclides(&mut nuclides, cli.nuclide_order);
+
+    // just the number of records that would be written, nothing else
+    if cli.count_only {
+        for nuclide in &nuclides {
+            println!("{} {}", nuclide.name, nuclide.records.len());
+        }
         return Ok(());
     }
 
-    // sort the sources by name because why not
-    nuclides.sort_by_key(|n| n.name.clone());
+    if cli.append {
+        if let Some((name, _)) = enabled_outputs(&cli)
+            .into_iter()
+            .find(|(_, extension)| !APPENDABLE.contains(extension))
+        {
+            bail!("--append is not supported for {name} output, only text, CSV, and MCNP");
+        }
+    }
 
-    let path = Path::new(&cli.output);
+    let output = cli.output_path();
+    let path = output.as_path();
 
     // Gnerate a table for printing/writing
-    let table = table::Table::new(&nuclides);
-    if !cli.quiet {
-        table.print(cli.no_colour);
+    let table = timings.time("Table", || build_table(&cli, &nuclides));
+    if cli.raw_lines {
+        print!("{}", table::raw_lines(&nuclides));
+    } else if !cli.quiet && !cli.diff {
+        table.print(cli.colour_disabled(), cli.width);
+    }
+
+    if cli.diff {
+        let [a, b] = nuclides.as_slice() else {
+            bail!("--diff requires exactly two nuclides, found {}", nuclides.len())
+        };
+        print!("{}", table::diff_table(a, b, cli.diff_tolerance));
+    }
+
+    // always printed, --quiet only applies to logging and the table
+    if cli.summary {
+        for nuclide in &nuclides {
+            println!("{}", table::summary_line(nuclide));
+        }
+    }
+
+    if cli.stats {
+        print!("{}", table::stats_table(&nuclides));
     }
 
+    if cli.norm_report {
+        print!("{}", table::norm_table(&nuclides));
+    }
+
+    if cli.plot {
+        plot::print(&nuclides, cli.colour_disabled(), cli.width);
+    }
+
+    if cli.group_shells {
+        match cli.rad.contains(&wrappers::CliRadType::Xray) {
+            true => print!("{}", table::shell_table(&nuclides, cli.shell_tolerance)),
+            false => warn!("--group-shells only applies to x-ray records, use --rad xray"),
+        }
+    }
+
+    if cli.dry_run {
+        dry_run(&cli, &nuclides, path);
+    } else if cli.split || cli.per_rad_files {
+        for (nuclides, path) in output_sets(&cli, &nuclides, path) {
+            let table = build_table(&cli, &nuclides);
+            write_outputs(&cli, &nuclides, &table, &path, &mut timings)?;
+        }
+    } else {
+        write_outputs(&cli, &nuclides, &table, path, &mut timings)?;
+    }
+
+    timings.report();
+    debug!("Done");
+    Ok(())
+}
+
+/// Extensions of the text based outputs that may be gzipped with --compress
+///
+/// Only the binary NumPy archive is left out.
+const COMPRESSIBLE: [&str; 17] = [
+    table::EXTENSION,
+    json::EXTENSION,
+    json::NDJSON_EXTENSION,
+    json::FLAT_EXTENSION,
+    yaml::EXTENSION,
+    toml_out::EXTENSION,
+    html::EXTENSION,
+    csv::PROCESSED_EXTENSION,
+    csv::TSV_EXTENSION,
+    csv::EXTENSION,
+    template::EXTENSION,
+    mcnp::EXTENSION,
+    serpent::EXTENSION,
+    tripoli::EXTENSION,
+    fluka::EXTENSION,
+    origen::EXTENSION,
+    geant4::EXTENSION,
+];
+
+/// Extensions of the outputs that may be appended to with --append
+const APPENDABLE: [&str; 4] = [
+    table::EXTENSION,
+    mcnp::EXTENSION,
+    csv::PROCESSED_EXTENSION,
+    csv::EXTENSION,
+];
+
+/// How output files are opened
+#[derive(Debug, Default, Copy, Clone)]
+struct FileOptions {
+    /// Gzip the file, keeping the original extension before `.gz`
+    compress: bool,
+    /// Append to an existing file rather than overwriting it
+    append: bool,
+}
+
+impl From<&cli::Cli> for FileOptions {
+    fn from(cli: &cli::Cli) -> Self {
+        Self {
+            compress: cli.compress,
+            append: cli.append,
+        }
+    }
+}
+
+impl FileOptions {
+    /// Full extension of the file, including `.gz` if compressed
+    fn extension(&self, extension: &str) -> String {
+        match self.compress {
+            true => format!("{extension}.gz"),
+            false => extension.to_string(),
+        }
+    }
+}
+
+/// Generate the table in the layout requested
+fn build_table(cli: &cli::Cli, nuclides: &[nuclide::NuclideData]) -> table::Table {
+    let options = table::TableOptions::from(cli);
+    match cli.combined {
+        true => table::Table::new_combined(nuclides, &options),
+        false => table::Table::new(nuclides, &options),
+    }
+}
+
+/// Output path prefix for a single nuclide, i.e. `<output>_<nuclide>`
+///
+/// Anything other than alphanumerics, `-`, and `_` in the name is replaced to
+/// keep the file name safe on every platform.
+fn split_path(path: &Path, nuclide: &nuclide::NuclideData) -> PathBuf {
+    let name = nuclide
+        .name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .collect::<String>();
+
+    let prefix = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{prefix}_{name}"))
+}
+
+/// Output path prefix for a single radiation type, i.e. `<output>.<rad>`
+///
+/// Every writer replaces the extension of the prefix, so a placeholder is
+/// added to keep the radiation type in the file name, e.g. `.gamma.json`.
+fn rad_path(path: &Path, rad_type: wrappers::CliRadType) -> PathBuf {
+    let name = rad_type
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+
+    let prefix = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{prefix}.{name}.ext"))
+}
+
+/// Copy of the nuclides with only the records of a single radiation type
+///
+/// Nuclides with no records of this type are left out entirely.
+fn rad_subset(
+    nuclides: &[nuclide::NuclideData],
+    rad_type: wrappers::CliRadType,
+) -> Vec<nuclide::NuclideData> {
+    nuclides
+        .iter()
+        .map(|n| {
+            let mut n = n.clone();
+            n.records.retain(|r| r.rad_type == rad_type);
+            n.all_records.retain(|r| r.rad_type == rad_type);
+            n
+        })
+        .filter(|n| !n.records.is_empty())
+        .collect()
+}
+
+/// Every set of nuclides to write and the path prefix for their files
+///
+/// This is a single set unless splitting by nuclide with --split, by radiation
+/// type with --per-rad-files, or both, e.g. `<output>_<nuclide>.<rad>.<ext>`.
+fn output_sets(
+    cli: &cli::Cli,
+    nuclides: &[nuclide::NuclideData],
+    path: &Path,
+) -> Vec<(Vec<nuclide::NuclideData>, PathBuf)> {
+    let sets = match cli.split {
+        true => nuclides
+            .iter()
+            .map(|n| (vec![n.clone()], split_path(path, n)))
+            .collect(),
+        false => vec![(nuclides.to_vec(), path.to_path_buf())],
+    };
+
+    match cli.per_rad_files {
+        true => sets
+            .into_iter()
+            .flat_map(|(nuclides, path)| {
+                cli.rad
+                    .iter()
+                    .map(|r| (rad_subset(&nuclides, *r), rad_path(&path, *r)))
+                    .filter(|(nuclides, _)| !nuclides.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+        false => sets,
+    }
+}
+
+/// Write every requested output file
+fn write_outputs(
+    cli: &cli::Cli,
+    nuclides: &[nuclide::NuclideData],
+    table: &table::Table,
+    path: &Path,
+    timings: &mut timing::Timings,
+) -> Result<()> {
+    let file = FileOptions::from(cli);
+
+    // every output is attempted, even if an earlier one failed
+    let mut results = Vec::new();
+    let mut write = |name: &'static str, f: &dyn Fn() -> Result<()>| {
+        results.push((name, timings.time(name, f)));
+    };
+
     if cli.text {
         debug!("Writing table to plain TEXT");
-        table.write(path)?;
+        write("Text", &|| table.write(path, file));
     }
 
     if cli.json {
         debug!("Writing to JSON");
-        json::write(&nuclides, path)?;
+        write("JSON", &|| json::write(nuclides, &cli.rad, path, file));
+    }
+
+    if cli.ndjson {
+        debug!("Writing to NDJSON");
+        write("NDJSON", &|| json::write_ndjson(nuclides, path, file));
+    }
+
+    if cli.flat_json {
+        debug!("Writing to flat JSON");
+        write("Flat JSON", &|| json::write_flat(nuclides, path, file));
+    }
+
+    if cli.yaml {
+        debug!("Writing to YAML");
+        write("YAML", &|| yaml::write(nuclides, path, file));
+    }
+
+    if cli.toml {
+        debug!("Writing to TOML");
+        write("TOML", &|| toml_out::write(nuclides, path, file));
+    }
+
+    if cli.html {
+        debug!("Writing HTML report");
+        write("HTML", &|| html::write(nuclides, path, file));
     }
 
     if cli.mcnp {
         debug!("Writing MCNP cards");
-        mcnp::write(&nuclides, cli.id, path)?;
+        write("MCNP", &|| mcnp::write(nuclides, &mcnp::McnpOptions::from(cli), path, file));
+    }
+
+    if cli.serpent {
+        debug!("Writing Serpent source definitions");
+        write("Serpent", &|| {
+            serpent::write(nuclides, &serpent::SerpentOptions::from(cli), path, file)
+        });
+    }
+
+    if cli.tripoli {
+        debug!("Writing TRIPOLI-4 spectrum blocks");
+        write("TRIPOLI-4", &|| tripoli::write(nuclides, path, file));
+    }
+
+    if cli.fluka {
+        debug!("Writing FLUKA spectra");
+        write("FLUKA", &|| fluka::write(nuclides, path, file));
+    }
+
+    if cli.origen {
+        debug!("Writing ORIGEN photon spectra");
+        write("ORIGEN", &|| origen::write(nuclides, cli.group_structure, path, file));
+    }
+
+    if cli.geant4 {
+        debug!("Writing Geant4 GPS macro");
+        write("Geant4", &|| geant4::write(nuclides, path, file));
+    }
+
+    if cli.npz {
+        debug!("Writing NumPy arrays");
+        write("NumPy", &|| npz::write(nuclides, path));
+    }
+
+    if cli.export_csv {
+        debug!("Writing processed records to CSV");
+        write("Processed CSV", &|| {
+            csv::write_processed(nuclides, path, file, cli.csv_delimiter, cli.csv_bom)
+        });
+    }
+
+    if cli.tsv {
+        debug!("Writing processed records to TSV");
+        write("TSV", &|| csv::write_tsv(nuclides, path, file, cli.csv_bom));
+    }
+
+    // only written alongside other files, otherwise just printed
+    if cli.norm_report && !enabled_outputs(cli).is_empty() {
+        debug!("Writing normalisation report");
+        // a fresh report for every run, even when appending to the outputs
+        let file = FileOptions {
+            append: false,
+            ..file
+        };
+        write("Norm report", &|| table::write_norm(nuclides, path, file));
+    }
+
+    if let Some(source) = &cli.template {
+        debug!("Writing records through template {}", source.display());
+        write("Template", &|| template::write(nuclides, source, path, file));
     }
 
     if cli.csv {
         debug!("Fetching raw csv");
-        csv::write(&nuclides, cli.rad.into(), path)?;
+        write("Raw CSV", &|| csv::write(nuclides, &cli.rad, path, file));
+    }
+
+    check_writes(results)
+}
+
+/// Log every failed output, carrying on with the rest
+///
+/// Some failures are reported as a general error, while all of them failing
+/// has its own exit code.
+fn check_writes(results: Vec<(&str, Result<()>)>) -> Result<()> {
+    let total = results.len();
+    let failed = results
+        .into_iter()
+        .filter_map(|(name, result)| result.err().map(|e| (name, e)))
+        .map(|(name, e)| {
+            error!("Unable to write {name} output: {e:#}");
+            name
+        })
+        .collect::<Vec<&str>>();
+
+    match failed.len() {
+        0 => Ok(()),
+        n if n == total => Err(Failure::WritesFailed.into()),
+        n => bail!("{n} of {total} outputs failed: {}", failed.join(", ")),
+    }
+}
+
+/// Let the user choose nuclides from a filterable list of those available
+///
+/// Any nuclides given on the command line are treated as elements to narrow
+/// down the list, e.g. "co" for only the cobalt isotopes.
+fn pick_nuclides(cli: &cli::Cli) -> Result<Vec<String>> {
+    let mut names = nuclide::available_names(cli)?;
+
+    if !cli.nuclides.is_empty() {
+        let elements = cli
+            .nuclides
+            .iter()
+            .map(|n| n.to_lowercase())
+            .collect::<Vec<String>>();
+        names.retain(|name| {
+            let symbol = name
+                .chars()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect::<String>();
+            elements.contains(&symbol.to_lowercase())
+        });
+    }
+
+    if names.is_empty() {
+        bail!("No nuclides available to choose from")
+    }
+
+    let selected = inquire::MultiSelect::new("Select nuclides (type to filter):", names)
+        .with_page_size(15)
+        .prompt()?;
+
+    debug!("Selected nuclides: {selected:?}");
+    Ok(selected)
+}
+
+/// Description of the pre-fetched data snapshot, recorded at build time
+const DATA_SNAPSHOT: Option<&str> = option_env!("DDATA_DATA_SNAPSHOT");
+
+/// Print the version and origin of the decay data in use
+///
+/// The IAEA API has no version of its own, so `--fetch` compares the list of
+/// available nuclides as a proxy for whether the pre-fetched data are stale.
+fn data_info(cli: &cli::Cli) -> Result<()> {
+    println!("ddata version     {}", env!("CARGO_PKG_VERSION"));
+    println!("Data source       IAEA LiveChart of Nuclides");
+    println!(
+        "Pre-fetched data  {}",
+        DATA_SNAPSHOT.unwrap_or("snapshot not recorded at build time")
+    );
+
+    let mut local = Vec::new();
+    for (rad_type, iaea_type) in wrappers::CliRadType::iaea_types(&wrappers::CliRadType::TYPES) {
+        let available = iaea::load_available(iaea_type)?;
+        println!("  {:<15} {} nuclides", rad_type.name(), available.len());
+        local.extend(available.iter().map(|n| n.name_with_state()));
+    }
+    local.sort();
+    local.dedup();
+
+    if !cli.fetch {
+        println!("\nUse --fetch to compare against the IAEA API");
+        return Ok(());
+    }
+
+    // no fallback here, the whole point is to reach the API
+    let mut remote = net::retry("available nuclides", iaea::fetch_available)?
+        .iter()
+        .map(|n| n.name_with_state())
+        .collect::<Vec<String>>();
+    remote.sort();
+    remote.dedup();
+
+    let added = remote.iter().filter(|n| !local.contains(n)).count();
+    let removed = local.iter().filter(|n| !remote.contains(n)).count();
+
+    match added + removed {
+        0 => println!("\nThe IAEA API lists the same {} nuclides", remote.len()),
+        _ => println!(
+            "\nThe IAEA API differs: {added} nuclides added, {removed} removed. \
+             Use --fetch for the latest data."
+        ),
     }
 
-    debug!("Done");
     Ok(())
 }
 
+/// Report the output files that would be written without writing them
+fn dry_run(cli: &cli::Cli, nuclides: &[nuclide::NuclideData], path: &Path) {
+    info!(
+        "Dry run: {} nuclides with {} records in total",
+        nuclides.len(),
+        nuclides.iter().map(|n| n.records.len()).sum::<usize>()
+    );
+
+    // one file per nuclide and/or radiation type if splitting the output
+    let paths = output_sets(cli, nuclides, path)
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect::<Vec<PathBuf>>();
+
+    let file = FileOptions::from(cli);
+    for (name, extension) in enabled_outputs(cli) {
+        let extension = match COMPRESSIBLE.contains(&extension) {
+            true => file.extension(extension),
+            false => extension.to_string(),
+        };
+
+        for path in &paths {
+            info!(
+                "Would write {name} output to {}",
+                path.with_extension(&extension).display()
+            );
+        }
+    }
+
+    if cli.norm_report && !enabled_outputs(cli).is_empty() {
+        for path in &paths {
+            info!(
+                "Would write normalisation report to {}",
+                path.with_extension(file.extension(table::NORM_EXTENSION)).display()
+            );
+        }
+    }
+}
+
+/// Extensions of --output that imply an output format
+const INFERRED_FORMATS: [(&str, wrappers::OutputFormat); 11] = [
+    (table::EXTENSION, wrappers::OutputFormat::Text),
+    (json::EXTENSION, wrappers::OutputFormat::Json),
+    (json::NDJSON_EXTENSION, wrappers::OutputFormat::Ndjson),
+    (yaml::EXTENSION, wrappers::OutputFormat::Yaml),
+    ("yml", wrappers::OutputFormat::Yaml),
+    (toml_out::EXTENSION, wrappers::OutputFormat::Toml),
+    (html::EXTENSION, wrappers::OutputFormat::Html),
+    (mcnp::EXTENSION, wrappers::OutputFormat::Mcnp),
+    (npz::EXTENSION, wrappers::OutputFormat::Npz),
+    (csv::TSV_EXTENSION, wrappers::OutputFormat::Tsv),
+    (csv::EXTENSION, wrappers::OutputFormat::Csv),
+];
+
+/// Enable the output format implied by the extension of --output, if any
+///
+/// The extension is removed from the prefix so that the file is named as
+/// given, e.g. `--output results.json` writes `results.json`. Unrecognised
+/// extensions are left as part of the prefix.
+fn infer_format(cli: &mut cli::Cli) {
+    let output = Path::new(&cli.output);
+    let Some(extension) = output.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+        return;
+    };
+
+    let Some((_, format)) = INFERRED_FORMATS.iter().find(|(e, _)| *e == extension) else {
+        return;
+    };
+
+    debug!("Writing {format} output based on the extension of {}", cli.output);
+    cli.output = output.with_extension("").to_string_lossy().to_string();
+    cli.format.push(*format);
+    cli.apply_formats();
+}
+
+/// Name and extension of every output format enabled on the command line
+fn enabled_outputs(cli: &cli::Cli) -> Vec<(&'static str, &'static str)> {
+    let outputs = [
+        (cli.text, "text", table::EXTENSION),
+        (cli.json, "JSON", json::EXTENSION),
+        (cli.ndjson, "NDJSON", json::NDJSON_EXTENSION),
+        (cli.flat_json, "flat JSON", json::FLAT_EXTENSION),
+        (cli.yaml, "YAML", yaml::EXTENSION),
+        (cli.toml, "TOML", toml_out::EXTENSION),
+        (cli.html, "HTML", html::EXTENSION),
+        (cli.mcnp, "MCNP", mcnp::EXTENSION),
+        (cli.serpent, "Serpent", serpent::EXTENSION),
+        (cli.tripoli, "TRIPOLI-4", tripoli::EXTENSION),
+        (cli.fluka, "FLUKA", fluka::EXTENSION),
+        (cli.origen, "ORIGEN", origen::EXTENSION),
+        (cli.geant4, "Geant4", geant4::EXTENSION),
+        (cli.npz, "NumPy", npz::EXTENSION),
+        (cli.export_csv, "processed CSV", csv::PROCESSED_EXTENSION),
+        (cli.tsv, "TSV", csv::TSV_EXTENSION),
+        (cli.csv, "raw CSV", csv::EXTENSION),
+        (cli.template.is_some(), "template", template::EXTENSION),
+    ];
+
+    outputs
+        .into_iter()
+        .filter(|(enabled, ..)| *enabled)
+        .map(|(_, name, extension)| (name, extension))
+        .collect()
+}
+
+/// Progress bar for slow network requests, only shown when fetching
+fn fetch_progress(cli: &cli::Cli, length: usize) -> Result<ProgressBar> {
+    // avoid clashing with the log output on stderr
+    if !cli.fetch || cli.quiet || cli.verbose > 0 {
+        return Ok(ProgressBar::hidden());
+    }
+
+    let progress = ProgressBar::new(length as u64);
+    progress.set_style(ProgressStyle::with_template(
+        "[{elapsed_precise}] {bar:40} {pos}/{len} {msg}",
+    )?);
+    Ok(progress)
+}
+
 /// Try to create a file, including all dirs, with a default to fallback on
-fn create_file_with_fallback(path: &Path, extension: &str, default: &str) -> Result<File> {
+///
+/// Compressed files are gzipped and keep the original extension, i.e. a JSON
+/// file becomes `<name>.json.gz`. Appending opens any existing file for
+/// writing at the end instead of truncating it. Call [OutputFile::finish] once
+/// everything is written.
+fn create_file_with_fallback(
+    path: &Path,
+    extension: &str,
+    default: &str,
+    file: FileOptions,
+) -> Result<OutputFile> {
     let mut p = path.to_path_buf();
 
     // Ensure all parent directories exist
@@ -90,11 +929,68 @@ fn create_file_with_fallback(path: &Path, extension: &str, default: &str) -> Res
         }
     }
 
+    let extension = file.extension(extension);
+    let default = match file.compress {
+        true => format!("{default}.gz"),
+        false => default.to_string(),
+    };
+
+    let open = |p: &Path| match file.append {
+        true => OpenOptions::new().create(true).append(true).open(p),
+        false => File::create(p),
+    };
+
     // Create the file, fall back to a default if not
-    let f = File::create(p.with_extension(extension)).or_else(|e| {
+    let f = open(&p.with_extension(extension)).or_else(|e| {
         warn!("{e}. Falling back to \"{default}\".",);
-        File::create(default).context("Unable to create fallback file")
+        open(Path::new(&default)).context("Unable to create fallback file")
     })?;
 
-    Ok(f)
+    Ok(match file.compress {
+        true => OutputFile::Gzip(GzEncoder::new(f, Compression::default())),
+        false => OutputFile::Plain(f),
+    })
+}
+
+/// An output file, which must be finished once everything is written
+///
+/// A gzip stream is only complete once the trailer is written, so finishing
+/// explicitly reports any error that dropping the encoder would hide.
+enum OutputFile {
+    Plain(File),
+    Gzip(GzEncoder<File>),
+}
+
+impl OutputFile {
+    /// Flush everything written, completing the gzip stream if compressed
+    fn finish(self) -> Result<()> {
+        match self {
+            OutputFile::Plain(mut f) => f.flush().context("Unable to write output file"),
+            OutputFile::Gzip(gz) => {
+                gz.finish().context("Unable to finish compressed output file")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutputFile::Plain(f) => f.write(buf),
+            OutputFile::Gzip(gz) => gz.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutputFile::Plain(f) => f.flush(),
+            OutputFile::Gzip(gz) => gz.flush(),
+        }
+    }
+}
+
+/// Check if an output file already exists and is not empty
+fn has_content(path: &Path, extension: &str, file: FileOptions) -> bool {
+    fs::metadata(path.with_extension(file.extension(extension))).is_ok_and(|m| m.len() > 0)
 }
@@ -1,88 +1,486 @@
 // internal
-use crate::create_file_with_fallback;
-use crate::nuclide::NuclideData;
+use crate::cli::Cli;
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::{NuclideData, TaggedRecord};
+use crate::wrappers::McnpDist;
 
 // standard lib
 use std::io::Write;
 use std::path::Path;
 
 // neutronics toolbox
-use ntools::iaea::Record;
 use ntools::utils::{f, ValueExt};
 
 // other
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use chrono::{SecondsFormat, Utc};
+use log::{debug, warn};
 
 const KEV_TO_MEV: f32 = 1.0e-03;
 
+/// File extension for MCNP cards
+pub const EXTENSION: &str = "i";
+
+/// Options controlling how the MCNP cards are generated
+#[derive(Debug, Clone)]
+pub struct McnpOptions {
+    /// Starting distribution number
+    pub id: usize,
+    /// Largest distribution number allowed
+    pub max_id: usize,
+    /// Width of histogram energy bins in keV, if binning records
+    pub bin_width: Option<f32>,
+    /// SI card distribution option for unbinned records
+    pub dist: McnpDist,
+    /// Number of significant figures for all values
+    pub precision: usize,
+    /// Number of distribution entries above which a warning is given
+    pub max_lines: usize,
+    /// Maximum line width of the cards
+    pub width: usize,
+    /// Number of spaces to indent continuation lines
+    pub indent: usize,
+    /// Re-parse the written cards to check the number of entries
+    pub verify: bool,
+    /// Maximum source energy in MeV, records above this are dropped
+    pub emax: Option<f32>,
+    /// Free text written as a comment block before all of the cards
+    pub note: Option<String>,
+}
+
+impl From<&Cli> for McnpOptions {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            id: cli.id,
+            max_id: cli.mcnp_max_id,
+            bin_width: cli.bin_width,
+            dist: cli.mcnp_dist,
+            precision: cli.precision.into(),
+            max_lines: cli.mcnp_max_lines,
+            width: cli.mcnp_width,
+            indent: cli.mcnp_indent,
+            verify: cli.verify,
+            emax: cli.mcnp_emax,
+            note: cli.mcnp_note.clone(),
+        }
+    }
+}
+
 /// Writes the mcnp cards to a file at the specified path.
-pub fn write(nuclides: &[NuclideData], id: usize, path: &Path) -> Result<()> {
-    let mut f = create_file_with_fallback(path, "i", "mcnp.i")?;
-    let cards = generate_mcnp_cards(nuclides, id);
+///
+/// Records are written using the chosen SI distribution option unless a bin
+/// width is given, in which case they are binned into a histogram. Cards are
+/// added to the end of an existing file if appending.
+pub fn write(
+    nuclides: &[NuclideData],
+    options: &McnpOptions,
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "mcnp.i", file)?;
+    let cards = generate_mcnp_cards(nuclides, options)?;
     f.write_all(cards.as_bytes())?;
-    Ok(())
+
+    if options.verify {
+        debug!("Verifying MCNP cards");
+        verify_cards(&cards, nuclides, options);
+    }
+
+    f.finish()
+}
+
+/// Parse the energies and probabilities of every SI/SP card pair
+///
+/// Continuation lines are joined onto the card they belong to, and anything
+/// that is not a number (card names, distribution options) is skipped.
+pub fn parse_cards(cards: &str) -> Vec<(Vec<f32>, Vec<f32>)> {
+    let mut joined: Vec<String> = Vec::new();
+    for line in cards.lines() {
+        match (line.starts_with(' '), joined.last_mut()) {
+            (true, Some(card)) => {
+                card.push(' ');
+                card.push_str(line.trim());
+            }
+            _ => joined.push(line.to_string()),
+        }
+    }
+
+    let values = |card: &str| {
+        card.split_whitespace()
+            .skip(1)
+            .filter_map(|v| v.parse::<f32>().ok())
+            .collect::<Vec<f32>>()
+    };
+
+    let mut distributions = Vec::new();
+    let mut energies = None;
+    for card in &joined {
+        if card.starts_with("si") {
+            energies = Some(values(card));
+        } else if card.starts_with("sp") {
+            if let Some(e) = energies.take() {
+                distributions.push((e, values(card)));
+            }
+        }
+    }
+
+    distributions
+}
+
+/// Check the parsed cards have the expected number of entries per nuclide
+fn verify_cards(cards: &str, nuclides: &[NuclideData], options: &McnpOptions) {
+    let expected = nuclides
+        .iter()
+        .map(|n| (n, expected_entries(n, options)))
+        .filter(|(_, entries)| *entries > 0)
+        .collect::<Vec<(&NuclideData, usize)>>();
+
+    let parsed = parse_cards(cards);
+    if parsed.len() != expected.len() {
+        warn!(
+            "Expected {} MCNP distributions but found {}",
+            expected.len(),
+            parsed.len()
+        );
+    }
+
+    for ((nuclide, entries), (energies, probabilities)) in expected.iter().zip(&parsed) {
+        if energies.len() != *entries || probabilities.len() != *entries {
+            warn!(
+                "{} MCNP cards have {} energies and {} probabilities, expected {}",
+                nuclide.name,
+                energies.len(),
+                probabilities.len(),
+                entries
+            );
+        }
+    }
+}
+
+/// Number of entries on both the SI and SP cards for a nuclide
+fn expected_entries(nuclide: &NuclideData, options: &McnpOptions) -> usize {
+    match options.bin_width {
+        // bin boundaries include the lowest edge
+        Some(width) => match nuclide.rebin(width).map_or(0, |bins| bins.len()) {
+            0 => 0,
+            n => n + 1,
+        },
+        None => {
+            let records = valid_records(nuclide, options);
+            match records.is_empty() {
+                true => 0,
+                false => line_entries(&records, options).map_or(0, |(_, e, _)| e.len()),
+            }
+        }
+    }
+}
+
+/// Records with both an energy and intensity, up to any maximum energy
+fn valid_records<'a>(nuclide: &'a NuclideData, options: &McnpOptions) -> Vec<&'a TaggedRecord> {
+    nuclide
+        .valid_lines()
+        .into_iter()
+        .filter(|record| match options.emax {
+            Some(emax) => record.energy.unwrap() * KEV_TO_MEV <= emax,
+            None => true,
+        })
+        .collect()
+}
+
+/// Extra zero probability entry at the maximum energy, if one is needed
+///
+/// Discrete lines have no upper limit to cap, but histograms and density
+/// points get a final entry at `emax` so that the distribution ends exactly
+/// there.
+fn capping_energy(records: &[&TaggedRecord], options: &McnpOptions) -> Option<f32> {
+    let emax = options.emax?;
+    let highest = records
+        .iter()
+        .filter_map(|record| record.energy)
+        .fold(f32::MIN, f32::max)
+        * KEV_TO_MEV;
+
+    match options.dist != McnpDist::Discrete && emax > highest {
+        true => Some(emax),
+        false => None,
+    }
+}
+
+/// Distribution, SI energies in MeV, and SP probabilities for a set of lines
+///
+/// A histogram needs a bin around every line, so the edges are halfway
+/// between neighbouring lines and extend the same distance beyond the lowest
+/// and highest. The first edge has no probability, and every line keeps its
+/// own bin. Lines at the same energy share a bin, with their probabilities
+/// summed, as MCNP needs the bin edges to strictly increase. A single line has
+/// no neighbour to make a bin from, and is always written as a discrete line.
+fn line_entries(
+    records: &[&TaggedRecord],
+    options: &McnpOptions,
+) -> Result<(McnpDist, Vec<f32>, Vec<f32>)> {
+    let mut lines = records
+        .iter()
+        .map(|record| {
+            (
+                record.energy.unwrap() * KEV_TO_MEV,
+                record.intensity.unwrap() * 1e-2,
+            )
+        })
+        .collect::<Vec<(f32, f32)>>();
+
+    // Histogram bin boundaries must be in ascending order, and distinct
+    if options.dist == McnpDist::Histogram {
+        lines.sort_by(|a, b| a.0.total_cmp(&b.0));
+        lines = merge_equal_energies(lines);
+    }
+
+    let dist = match options.dist == McnpDist::Histogram && lines.len() == 1 {
+        true => McnpDist::Discrete,
+        false => options.dist,
+    };
+
+    let (lines, mut probabilities): (Vec<f32>, Vec<f32>) = lines.into_iter().unzip();
+    let mut energies = match dist {
+        McnpDist::Histogram => {
+            probabilities.insert(0, 0.0);
+            histogram_edges(&lines)
+        }
+        _ => lines,
+    };
+
+    // End exactly at any capping energy, with nothing above the last line
+    let cap = match dist {
+        McnpDist::Discrete => None,
+        _ => capping_energy(records, options),
+    };
+    if let Some(cap) = cap {
+        let last = energies.last_mut().unwrap();
+        match dist == McnpDist::Histogram && cap <= *last {
+            true => *last = cap,
+            false => {
+                energies.push(cap);
+                probabilities.push(0.0);
+            }
+        }
+    }
+
+    if dist == McnpDist::Histogram && energies.windows(2).any(|pair| pair[1] <= pair[0]) {
+        bail!("Histogram bin edges do not strictly increase, lines are too close together")
+    }
+
+    Ok((dist, energies, probabilities))
+}
+
+/// Sum the probabilities of sorted lines that have exactly the same energy
+pub fn merge_equal_energies(lines: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    let mut merged: Vec<(f32, f32)> = Vec::with_capacity(lines.len());
+    for (energy, probability) in lines {
+        match merged.last_mut() {
+            Some(last) if last.0 == energy => last.1 += probability,
+            _ => merged.push((energy, probability)),
+        }
+    }
+    merged
+}
+
+/// Bin edges halfway between sorted line energies, one more than the lines
+///
+/// The lowest edge is never below zero. Needs at least two lines.
+fn histogram_edges(lines: &[f32]) -> Vec<f32> {
+    let n = lines.len();
+    let lower = (lines[0] - 0.5 * (lines[1] - lines[0])).max(0.0);
+    let upper = lines[n - 1] + 0.5 * (lines[n - 1] - lines[n - 2]);
+
+    std::iter::once(lower)
+        .chain(lines.windows(2).map(|pair| 0.5 * (pair[0] + pair[1])))
+        .chain(std::iter::once(upper))
+        .collect()
 }
 
 /// Make source distribution cards for every nuclide
-fn generate_mcnp_cards(nuclides: &[NuclideData], id: usize) -> String {
-    let mut card = String::new();
+///
+/// Distribution numbers increase from the starting id, and it is an error for
+/// any to go beyond the maximum id.
+fn generate_mcnp_cards(nuclides: &[NuclideData], options: &McnpOptions) -> Result<String> {
+    if options.id < 1 {
+        warn!("MCNP distribution numbers start at {}, expected at least 1", options.id);
+    }
+
+    let last = options.id + nuclides.len().saturating_sub(1);
+    if last > options.max_id {
+        bail!(
+            "MCNP distribution numbers {}-{last} for {} nuclides exceed --mcnp-max-id {}",
+            options.id,
+            nuclides.len(),
+            options.max_id
+        )
+    }
+
+    // record where the cards came from, then any notes from the user
+    let mut card = f!(
+        "c Generated by ddata v{} on {}\n",
+        env!("CARGO_PKG_VERSION"),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+    );
+    if let Some(note) = &options.note {
+        card += &comment_block(note, options.width);
+    }
+    card += "c\n";
+
     for (i, nuclide) in nuclides.iter().enumerate() {
-        card += &nuclide_distribution(nuclide, id + i);
+        let id = options.id + i;
+        if nuclide.with_metadata {
+            card += &f!("c {}: {}\n", nuclide.name, nuclide.metadata());
+        }
+        card += &match options.bin_width {
+            Some(width) => histogram_distribution(nuclide, id, width, options)?,
+            None => nuclide_distribution(nuclide, id, options)?,
+        };
     }
-    card
+    Ok(card)
 }
 
 /// Make a single source distribution for a nuclide
-fn nuclide_distribution(nuclide: &NuclideData, id: usize) -> String {
+fn nuclide_distribution(
+    nuclide: &NuclideData,
+    id: usize,
+    options: &McnpOptions,
+) -> Result<String> {
+    let precision = options.precision;
+
     // Need to filer out any nonsense values where energy/intensity is None
-    let filtered_records = nuclide
-        .records
-        .iter()
-        .filter(|record| record.energy.is_some() && record.intensity.is_some())
-        .collect::<Vec<&Record>>();
+    let filtered_records = valid_records(nuclide, options);
 
     if filtered_records.is_empty() {
-        return f!("c {} records contained no valid decay data\n", nuclide.name);
+        return Ok(f!("c {} records contained no valid decay data\n", nuclide.name));
     }
 
+    if filtered_records.len() > options.max_lines {
+        warn!(
+            "{} has {} MCNP distribution entries (limit {}), consider --bin-width",
+            nuclide.name,
+            filtered_records.len(),
+            options.max_lines
+        );
+    }
+
+    // Only the lines actually written count towards a capped source
+    let norm = match options.emax {
+        Some(_) => filtered_records
+            .iter()
+            .fold(0.0, |acc, r| acc + r.intensity.unwrap() as f64 * 1e-2),
+        None => nuclide.norm(), // this is already ignoring None intensities
+    };
+
     // Create a comment line with nuclide name and normalization factor
     let comment = f!(
         "sc{id:<5} {} decay data, norm = {} particles/decay",
         nuclide.name,
-        nuclide.norm().sci(5, 2) // this is already ignoring None intensities
+        norm.sci(precision, 2)
     );
 
-    // Create the SI card with energy values
+    // Create the SI card with energy values, ending at any capping energy
+    let (dist, energies, probabilities) = line_entries(&filtered_records, options)
+        .with_context(|| f!("Invalid MCNP distribution for {}", nuclide.name))?;
+    let energies = energies
+        .iter()
+        .map(|energy| energy.sci(precision, 2))
+        .collect::<Vec<String>>();
+    let si_card = f!("si{id} {} {}", dist.letter(), energies.join(" "));
+
+    // Create the SP card with intensity values, zero for any empty bins
+    let probabilities = probabilities
+        .iter()
+        .map(|p| match *p == 0.0 {
+            true => "0".to_string(),
+            false => p.sci(precision, 2),
+        })
+        .collect::<Vec<String>>();
+    let sp_card = f!("sp{id:<6}{}", probabilities.join(" "));
+
+    // Combine the comment, SI card, and SP card with proper formatting
+    let indent = " ".repeat(options.indent);
+    Ok(f!(
+        "{}\n{}\n{}\nc\n",
+        comment,
+        wrap_text(si_card, options.width, &indent),
+        wrap_text(sp_card, options.width, &indent)
+    ))
+}
+
+/// Make a single histogram source distribution for a nuclide
+fn histogram_distribution(
+    nuclide: &NuclideData,
+    id: usize,
+    width: f32,
+    options: &McnpOptions,
+) -> Result<String> {
+    let precision = options.precision;
+    let bins = nuclide.rebin(width)?;
+
+    if bins.is_empty() {
+        return Ok(f!("c {} records contained no valid decay data\n", nuclide.name));
+    }
+
+    // Create a comment line with nuclide name and normalization factor
+    let comment = f!(
+        "sc{id:<5} {} decay data, norm = {} particles/decay",
+        nuclide.name,
+        nuclide.norm().sci(precision, 2)
+    );
+
+    // Create the SI card with the bin boundaries, starting at the lowest edge
+    let lower_edge = (bins[0].0 - 0.5 * width) * KEV_TO_MEV;
     let si_card = f!(
-        "si{id} L {}",
-        filtered_records
-            .iter()
-            .map(|record| (record.energy.unwrap() * KEV_TO_MEV).sci(5, 2))
+        "si{id} H {} {}",
+        lower_edge.sci(precision, 2),
+        bins.iter()
+            .map(|(centre, _)| ((centre + 0.5 * width) * KEV_TO_MEV).sci(precision, 2))
             .collect::<Vec<String>>()
             .join(" ")
     );
 
-    // Create the SP card with intensity values
+    // Create the SP card with bin probabilities, the first entry must be zero
     let sp_card = f!(
-        "sp{id:<6}{}",
-        filtered_records
-            .iter()
-            .map(|record| (record.intensity.unwrap() * 1e-2).sci(5, 2))
+        "sp{id:<6}0 {}",
+        bins.iter()
+            .map(|(_, intensity)| (intensity * 1e-2).sci(precision, 2))
             .collect::<Vec<String>>()
             .join(" ")
     );
 
-    // Combine the comment, SI card, and SP card with proper formatting
-    f!(
+    let indent = " ".repeat(options.indent);
+    Ok(f!(
         "{}\n{}\n{}\nc\n",
         comment,
-        wrap_text(si_card, 80, "        "),
-        wrap_text(sp_card, 80, "        ")
-    )
+        wrap_text(si_card, options.width, &indent),
+        wrap_text(sp_card, options.width, &indent)
+    ))
+}
+
+/// Turn free text into MCNP comment lines, wrapped to the card width
+///
+/// Every line of the text is kept as its own comment, and long lines are
+/// wrapped onto further comment lines.
+fn comment_block(text: &str, width: usize) -> String {
+    let options = textwrap::Options::new(width)
+        .initial_indent("c ")
+        .subsequent_indent("c ")
+        .word_splitter(textwrap::WordSplitter::NoHyphenation);
+
+    let mut block = String::new();
+    for line in text.lines() {
+        match line.trim().is_empty() {
+            true => block += "c",
+            false => block += &textwrap::fill(line.trim_end(), &options),
+        }
+        block += "\n";
+    }
+    block
 }
 
 // wrap everything to a fixed number of characters for mcnp
-fn wrap_text(text: String, width: usize, subsequent_indent: &str) -> String {
+pub fn wrap_text(text: String, width: usize, subsequent_indent: &str) -> String {
     let options = textwrap::Options::new(width)
         .initial_indent("")
         .subsequent_indent(subsequent_indent)
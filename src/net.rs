@@ -0,0 +1,121 @@
+// internal
+use crate::cli::Cli;
+use crate::wrappers::{CliRadType, DataSource};
+
+// standard lib
+use std::fmt::Display;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+// neutronics toolbox
+use ntools::iaea::{self, Nuclide, RadType, RecordSet};
+
+// other
+use anyhow::Result;
+use log::{debug, warn};
+
+/// Retry policy shared by every request to the IAEA API
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Number of retries and the initial delay between them
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every retry after
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl From<&Cli> for RetryPolicy {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            retries: cli.retries,
+            delay: Duration::from_millis(cli.retry_delay),
+        }
+    }
+}
+
+/// Set the retry policy for all requests, only the first call has any effect
+pub fn init(cli: &Cli) {
+    let _ = POLICY.set(RetryPolicy::from(cli));
+}
+
+/// Retry a request with exponential backoff
+pub fn retry<T, E: Display>(
+    description: &str,
+    mut request: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let policy = POLICY.get().copied().unwrap_or_default();
+    let mut delay = policy.delay;
+
+    for attempt in 1..=policy.retries {
+        match request() {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                warn!(
+                    "Request for {description} failed ({e}), retry {attempt}/{} in {} ms",
+                    policy.retries,
+                    delay.as_millis()
+                );
+                thread::sleep(delay);
+                delay *= 2;
+            }
+        }
+    }
+
+    request()
+}
+
+/// Fetch the records of a nuclide, falling back to pre-fetched data on failure
+///
+/// The pre-fetched data show whether any records are expected at all, so an
+/// empty response is only retried if there should have been something. The
+/// records are returned with where they actually came from.
+pub fn fetch_nuclide(nuclide: &Nuclide, rad_type: RadType) -> Option<(RecordSet, DataSource)> {
+    let Some(local) = iaea::load_nuclide(nuclide.clone(), rad_type) else {
+        return iaea::fetch_nuclide(nuclide.clone(), rad_type).map(|r| (r, DataSource::Iaea));
+    };
+
+    let description = format!("{rad_type:?} records of {}", nuclide.name_with_state());
+    let fetched = retry(&description, || {
+        iaea::fetch_nuclide(nuclide.clone(), rad_type).ok_or("no records returned")
+    });
+
+    match fetched {
+        Ok(records) => Some((records, DataSource::Iaea)),
+        Err(_) => {
+            warn!("Falling back to pre-fetched {description}");
+            Some((local, DataSource::Local))
+        }
+    }
+}
+
+/// Fetch the raw CSV data for a nuclide
+pub fn fetch_csv(nuclide: &String, rad_type: RadType) -> Result<String> {
+    retry(&format!("{rad_type:?} CSV of {nuclide}"), || {
+        iaea::fetch_csv(nuclide, rad_type)
+    })
+}
+
+/// Fetch all available nuclides, falling back to pre-fetched data on failure
+pub fn fetch_available() -> Result<Vec<Nuclide>> {
+    retry("available nuclides", iaea::fetch_available).or_else(|e| {
+        warn!("{e}. Falling back to pre-fetched nuclide list.");
+        let mut available = Vec::new();
+        for (rad_type, iaea_type) in CliRadType::iaea_types(&CliRadType::TYPES) {
+            debug!("Loading pre-fetched {} nuclides", rad_type.name());
+            available.extend(iaea::load_available(iaea_type)?);
+        }
+        Ok(available)
+    })
+}
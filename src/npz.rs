@@ -0,0 +1,95 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::f;
+
+// other
+use anyhow::{Context, Result};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// File extension for NumPy archives
+pub const EXTENSION: &str = "npz";
+
+/// Writes the energies and intensities as NumPy arrays to a `.npz` archive.
+///
+/// Every nuclide has a `<name>_energy` array in keV and a `<name>_intensity`
+/// array, so `numpy.load(file)["Co60_energy"]` gives the energies of Co60.
+/// Missing values are written as NaN to keep the arrays aligned.
+///
+/// # Arguments
+///
+/// * `path` - The path where the archive should be written.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write(nuclides: &[NuclideData], path: &Path) -> Result<()> {
+    // the zip writer needs to seek, so build the archive in memory first
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for nuclide in nuclides {
+        let energy = nuclide
+            .records
+            .iter()
+            .map(|r| r.energy)
+            .collect::<Vec<Option<f32>>>();
+
+        let intensity = nuclide
+            .records
+            .iter()
+            .map(|r| nuclide.in_units(r.intensity))
+            .collect::<Vec<Option<f32>>>();
+
+        for (key, values) in [("energy", energy), ("intensity", intensity)] {
+            archive
+                .start_file(f!("{}_{key}.npy", nuclide.name), options)
+                .with_context(|| f!("Unable to add {} {key} to the archive", nuclide.name))?;
+            archive.write_all(&npy_array(&values))?;
+        }
+    }
+
+    let bytes = archive
+        .finish()
+        .context("Unable to finish the NumPy archive")?
+        .into_inner();
+
+    let mut f =
+        create_file_with_fallback(path, EXTENSION, "decay_data.npz", FileOptions::default())?;
+    f.write_all(&bytes)?;
+    f.finish()
+}
+
+/// Make a one dimensional `.npy` array of little-endian doubles
+///
+/// Follows version 1.0 of the format, where the header is padded with spaces
+/// so that the data start on a multiple of 64 bytes.
+fn npy_array(values: &[Option<f32>]) -> Vec<u8> {
+    let mut header = f!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({},), }}",
+        values.len()
+    );
+
+    // 6 byte magic string, 2 byte version, 2 byte header length, and a newline
+    let padding = 64 - (10 + header.len() + 1) % 64;
+    header += &" ".repeat(padding % 64);
+    header += "\n";
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+
+    for value in values {
+        let value = value.map_or(f64::NAN, |v| v as f64);
+        bytes.extend(value.to_le_bytes());
+    }
+
+    bytes
+}
@@ -1,44 +1,106 @@
 // internal
+use crate::aliases;
 use crate::cli::Cli;
-use crate::wrappers::Property;
+use crate::net;
+use crate::wrappers::{CliRadType, DataSource, EnergyUnit, IntensityUnits, NuclideOrder, Property};
+
+// standard lib
+use std::collections::BTreeSet;
+use std::ops::{Deref, DerefMut};
 
 // neutronics toolbox
 use ntools::iaea::{self, IsomerState, Nuclide, Record, RecordSet};
 
 // other
-use anyhow::{bail, Result};
-use log::{debug, error, trace};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, error, log, trace, warn, Level};
+use serde::de::{self, Deserialize, Deserializer};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
+/// Largest number of bins [NuclideData::rebin] will make for one nuclide
+pub const MAX_BINS: usize = 100_000;
+
 /// Parse the user provided nuclides into something useful
-pub fn parse_nuclides(cli: &Cli) -> Result<Vec<NuclideData>> {
+///
+/// Inputs are first expanded into ground states, which only needs the list of
+/// available nuclides. Anything else fetched per nuclide, i.e. excited states
+/// and decay chains, stops as soon as there are more than `limit` nuclides.
+pub fn parse_nuclides(cli: &Cli, limit: Option<usize>) -> Result<Vec<NuclideData>> {
     debug!("Command line nuclides: {:?}", cli.nuclides);
 
+    let inputs = canonical_inputs(&cli.nuclides);
+
     // collect all unstable nuclides that also exist in the IAEA data
-    let mut nuclide_data = cli
-        .nuclides
+    let expanded = inputs
         .iter()
-        .filter_map(|n| Nuclide::try_from(n).ok())
-        .filter_map(|n| expand_elements(n, cli).ok())
-        .flatten()
-        .map(|n| NuclideData {
-            name: n.name_with_state(),
-            nuclide: n,
-            records: Vec::new(),
-        })
-        .collect::<Vec<NuclideData>>();
+        .map(|n| expand_input(n, cli))
+        .collect::<Result<Vec<Vec<Nuclide>>>>()?;
 
-    trace!("Nuclides sorted by name");
-    nuclide_data.sort_by_key(|n| n.name.clone());
+    // explain inputs that will never have any decay data
+    let mut unknown = Vec::new();
+    for input in &inputs {
+        unknown.push(check_input(input, cli)?);
+    }
 
-    trace!("Removing duplicates");
-    nuclide_data.dedup();
+    if cli.strict {
+        let failures = cli
+            .nuclides
+            .iter()
+            .zip(expanded.iter().zip(&unknown))
+            .filter(|(_, (nuclides, unknown))| nuclides.is_empty() || **unknown)
+            .map(|(input, _)| input.as_str())
+            .collect::<Vec<&str>>();
+
+        if !failures.is_empty() {
+            bail!("Unrecognised or unmatched nuclides: {}", failures.join(", "))
+        }
+    }
+
+    // nothing has been fetched for any single nuclide yet
+    let mut limit = NuclideLimit::new(limit);
+    for nuclide in expanded.iter().flatten() {
+        limit.add(&canonical_state(nuclide.clone()))?;
+    }
+
+    let mut nuclides = Vec::new();
+    for (input, ground_states) in inputs.iter().zip(expanded) {
+        let isomers = cli.include_isomers && is_group(input);
+        for nuclide in ground_states.into_iter().map(canonical_state) {
+            if isomers {
+                for isomer in excited_states(&nuclide, cli) {
+                    limit.add(&isomer)?;
+                    nuclides.push(isomer);
+                }
+            }
+            nuclides.push(nuclide);
+        }
+    }
+
+    let mut nuclide_data = Vec::new();
+    for nuclide in nuclides {
+        let chain = match cli.chain {
+            true => expand_chain(&nuclide, cli),
+            false => vec![(nuclide, 1.0)],
+        };
+
+        for (n, fraction) in chain {
+            limit.add(&n)?;
+            let mut data = NuclideData::new(n);
+            data.chain_fraction = fraction;
+            nuclide_data.push(data);
+        }
+    }
+
+    dedup_nuclides(&mut nuclide_data);
     if nuclide_data.is_empty() {
         error!(
             "No {} decay data found for any requested nuclide",
-            cli.rad.name()
+            cli.rad
+                .iter()
+                .map(|r| r.name())
+                .collect::<Vec<&str>>()
+                .join("/")
         );
-        bail!("No decay data found")
     }
 
     debug!(
@@ -52,26 +114,440 @@ pub fn parse_nuclides(cli: &Cli) -> Result<Vec<NuclideData>> {
     Ok(nuclide_data)
 }
 
-/// Expand elements into their nuclides
-fn expand_elements(nuclide: Nuclide, cli: &Cli) -> Result<Vec<Nuclide>> {
-    // ok to do in a loop, this is in a oncecell and only ever loaded once
+/// Map any alternate spellings onto the usual notation
+fn canonical_inputs(nuclides: &[String]) -> Vec<String> {
+    nuclides.iter().map(|n| aliases::canonical(n)).collect()
+}
+
+/// Sort nuclides by name and remove any duplicates
+///
+/// Different spellings of one nuclide end up with the same name once in their
+/// canonical state, so only one entry is kept for each.
+fn dedup_nuclides(nuclide_data: &mut Vec<NuclideData>) {
+    trace!("Nuclides sorted by name");
+    nuclide_data.sort_by_key(|n| n.name.clone());
+
+    trace!("Removing duplicates");
+    nuclide_data.dedup();
+}
+
+/// Running count of unique nuclides, to stop before too many are fetched
+struct NuclideLimit {
+    /// Maximum number of nuclides, if any
+    max: Option<usize>,
+    /// Names of every nuclide counted so far
+    names: BTreeSet<String>,
+}
+
+impl NuclideLimit {
+    fn new(max: Option<usize>) -> Self {
+        Self {
+            max,
+            names: BTreeSet::new(),
+        }
+    }
+
+    /// Count a nuclide, failing once there are more than the maximum
+    fn add(&mut self, nuclide: &Nuclide) -> Result<()> {
+        let Some(max) = self.max else {
+            return Ok(());
+        };
+
+        self.names.insert(nuclide.name_with_state());
+        if self.names.len() > max {
+            bail!(
+                "At least {} nuclides would be fetched from the IAEA API, more than \
+                 --max-nuclides {max}. Use --yes to continue anyway.",
+                self.names.len()
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Check for an input that expands into several nuclides, not a single one
+///
+/// Only these include excited states with --include-isomers.
+fn is_group(input: &str) -> bool {
+    input.contains(':')
+        || is_wildcard(input)
+        || Nuclide::try_from(&input.to_string()).is_ok_and(|n| n.isotope == 0)
+}
+
+/// Treat an explicit `m0` state as the ground state
+///
+/// Otherwise "Co60m0" and "Co60" could be kept as separate entries by the
+/// dedup in [parse_nuclides], despite being the same nuclide.
+fn canonical_state(mut nuclide: Nuclide) -> Nuclide {
+    if matches!(nuclide.state, IsomerState::Excited(0)) {
+        trace!("Using the ground state for {}", nuclide.name_with_state());
+        nuclide.state = IsomerState::Ground;
+    }
+    nuclide
+}
+
+/// Sort nuclides by name, atomic number, or mass number
+///
+/// Mass numbers and states are compared numerically rather than as part of the
+/// name, and anything without a known atomic number is last for
+/// [NuclideOrder::Z].
+pub fn sort_nuclides(nuclides: &mut [NuclideData], order: NuclideOrder) {
+    let z = |n: &NuclideData| aliases::atomic_number(&n.nuclide.symbol).unwrap_or(u32::MAX);
+    let state = |n: &NuclideData| n.metadata().state;
+
+    match order {
+        NuclideOrder::Name => nuclides.sort_by_key(|n| {
+            (n.nuclide.symbol.to_lowercase(), n.nuclide.isotope, state(n), n.name.clone())
+        }),
+        NuclideOrder::Z => {
+            nuclides.sort_by_key(|n| (z(n), n.nuclide.isotope, state(n), n.name.clone()))
+        }
+        NuclideOrder::Mass => {
+            nuclides.sort_by_key(|n| (n.nuclide.isotope, z(n), state(n), n.name.clone()))
+        }
+    }
+}
+
+/// Remove any nuclides named in --exclude
+///
+/// Names are made canonical and compared as nuclides, so that "cs-137" and
+/// "Cs137m0" both remove Cs137. Names that do not match anything are skipped.
+pub fn exclude_nuclides(nuclides: &mut Vec<NuclideData>, exclude: &[String]) {
+    for name in exclude.iter().map(|e| aliases::canonical(e)) {
+        let Ok(nuclide) = Nuclide::try_from(&name).map(canonical_state) else {
+            debug!("Nothing to exclude for {name}, not a recognised nuclide");
+            continue;
+        };
+
+        let before = nuclides.len();
+        nuclides.retain(|n| n.nuclide != nuclide);
+        match nuclides.len() < before {
+            true => debug!("Excluded {}", nuclide.name_with_state()),
+            false => debug!("Nothing to exclude for {name}, not in the requested nuclides"),
+        }
+    }
+}
+
+/// Parse the `name:weight` pairs given to --weights
+///
+/// Weights are relative activities, so must be finite and not negative.
+pub fn parse_weights(weights: &[String]) -> Result<Vec<(Nuclide, f32)>> {
+    weights
+        .iter()
+        .map(|w| {
+            let Some((name, weight)) = w.split_once(':') else {
+                bail!("Invalid weight \"{w}\", expected e.g. co60:0.5")
+            };
+            // same spelling and state rules as the nuclides themselves
+            let nuclide = Nuclide::try_from(&aliases::canonical(name.trim()))
+                .map(canonical_state)
+                .map_err(|_| anyhow!("Unrecognised nuclide \"{name}\" in --weights"))?;
+            let weight = weight
+                .trim()
+                .parse::<f32>()
+                .with_context(|| format!("Invalid weight for {name} in \"{w}\""))?;
+            if !weight.is_finite() || weight < 0.0 {
+                bail!("Invalid weight for {name} in \"{w}\", must be finite and not negative")
+            }
+            Ok((nuclide, weight))
+        })
+        .collect()
+}
+
+/// Combine the records of every nuclide into a single "mixture"
+///
+/// All records are kept, with intensities multiplied by the weight of their
+/// nuclide. Nuclides without a weight have a weight of 1, i.e. equal activity.
+/// The unfiltered records are merged in the same way. The mixture otherwise
+/// takes the properties of the first nuclide.
+pub fn combine(nuclides: &[NuclideData], weights: &[(Nuclide, f32)]) -> Option<NuclideData> {
+    for (nuclide, _) in weights {
+        if !nuclides.iter().any(|n| n.nuclide == *nuclide) {
+            warn!("Weight given for {}, which is not in the mixture", nuclide.name_with_state());
+        }
+    }
+
+    let mut mixture = nuclides.first()?.clone();
+    mixture.name = "mixture".to_string();
+    // a mixture has no single atomic number, mass number, or half-life
+    mixture.with_metadata = false;
+    mixture.records = weighted_records(nuclides, weights, |n| &n.records);
+    mixture.all_records = weighted_records(nuclides, weights, |n| &n.all_records);
+
+    Some(mixture)
+}
+
+/// Records of every nuclide with intensities multiplied by their weight
+fn weighted_records(
+    nuclides: &[NuclideData],
+    weights: &[(Nuclide, f32)],
+    records: impl Fn(&NuclideData) -> &Vec<TaggedRecord>,
+) -> Vec<TaggedRecord> {
+    nuclides
+        .iter()
+        .flat_map(|n| {
+            let weight = weights
+                .iter()
+                .find(|(nuclide, _)| *nuclide == n.nuclide)
+                .map_or(1.0, |(_, weight)| *weight);
+
+            records(n).iter().cloned().map(move |mut record| {
+                record.intensity = record.intensity.map(|i| i * weight);
+                record
+            })
+        })
+        .collect()
+}
+
+/// Warn about a user input that is either stable or not a valid nuclide
+///
+/// Elements, mass ranges, and wildcards are expanded from the available data,
+/// so only specific nuclides are checked. Returns whether the nuclide is
+/// unknown, which the caller reports as an error with --strict.
+fn check_input(input: &str, cli: &Cli) -> Result<bool> {
+    if input.contains(':') || is_wildcard(input) {
+        return Ok(false);
+    }
+
+    let Ok(nuclide) = Nuclide::try_from(&input.to_string()) else {
+        warn!("Unrecognised nuclide \"{input}\", check for typos");
+        return Ok(false);
+    };
+
+    if nuclide.isotope == 0 {
+        return Ok(false);
+    }
+
+    Ok(match availability(&nuclide, cli.fetch)? {
+        Availability::HasData => false,
+        Availability::Stable => {
+            warn!("{} is stable; no decay data exists", nuclide.name());
+            false
+        }
+        // reported along with every other failure by the caller
+        Availability::Unknown if cli.strict => true,
+        Availability::Unknown => {
+            warn!("Unknown nuclide {}, no decay data found; check for typos", nuclide.name());
+            true
+        }
+    })
+}
+
+/// What the available data say about a specific nuclide
+enum Availability {
+    /// Decay data exist for at least one radiation type
+    HasData,
+    /// No decay data, but between isotopes of the element that have some
+    Stable,
+    /// No decay data, and outside the known isotopes of the element
+    Unknown,
+}
+
+/// Check whether a nuclide has decay data, or is likely to be stable
+///
+/// Stable isotopes are not in the IAEA lists, so a nuclide without data is only
+/// taken to be stable if its mass number is within the range of isotopes of
+/// the element that do have data. Anything outside, e.g. "co06", is unknown.
+fn availability(nuclide: &Nuclide, fetch: bool) -> Result<Availability> {
+    let available = match fetch {
+        false => {
+            let mut available = Vec::new();
+            for (_, rad_type) in CliRadType::iaea_types(&CliRadType::TYPES) {
+                available.extend(iaea::load_available(rad_type)?);
+            }
+            available
+        }
+        true => net::fetch_available()?,
+    };
+
+    let isotopes = available
+        .iter()
+        .filter(|n| n.symbol == nuclide.symbol)
+        .map(|n| n.isotope)
+        .collect::<Vec<u32>>();
+
+    let (Some(min), Some(max)) = (isotopes.iter().min(), isotopes.iter().max()) else {
+        return Ok(Availability::Unknown);
+    };
+
+    Ok(match isotopes.contains(&nuclide.isotope) {
+        true => Availability::HasData,
+        false if (*min..=*max).contains(&nuclide.isotope) => Availability::Stable,
+        false => Availability::Unknown,
+    })
+}
+
+/// Expand a single user input into all of the nuclides it refers to
+///
+/// Unknown nuclides are ignored, but malformed mass ranges are an error.
+fn expand_input(input: &str, cli: &Cli) -> Result<Vec<Nuclide>> {
+    if let Some((symbol, range)) = input.split_once(':') {
+        return expand_mass_range(symbol, range, cli);
+    }
+
+    if is_wildcard(input) {
+        return expand_wildcard(input, cli);
+    }
+
+    Ok(Nuclide::try_from(&input.to_string())
+        .ok()
+        .and_then(|n| expand_elements(n, cli).ok())
+        .unwrap_or_default())
+}
+
+/// Expand the `Symbol:Amin-Amax` syntax into all isotopes within the range
+fn expand_mass_range(symbol: &str, range: &str, cli: &Cli) -> Result<Vec<Nuclide>> {
+    let Some((min, max)) = range.split_once('-') else {
+        bail!("Invalid mass range \"{symbol}:{range}\", expected e.g. Cs:130-140")
+    };
+
+    let min = min
+        .trim()
+        .parse::<u32>()
+        .with_context(|| format!("Invalid minimum mass number in \"{symbol}:{range}\""))?;
+    let max = max
+        .trim()
+        .parse::<u32>()
+        .with_context(|| format!("Invalid maximum mass number in \"{symbol}:{range}\""))?;
+
+    if min > max {
+        bail!("Invalid mass range \"{symbol}:{range}\", minimum is greater than maximum")
+    }
+
+    let element = match Nuclide::try_from(&symbol.to_string()) {
+        Ok(n) if n.isotope == 0 => n,
+        _ => bail!("Unknown element \"{symbol}\" in mass range"),
+    };
+
+    debug!("Expanding {} isotopes with A = {min}-{max}", element.symbol);
+    Ok(expand_elements(element, cli)?
+        .into_iter()
+        .filter(|n| (min..=max).contains(&n.isotope))
+        .collect())
+}
+
+/// Expand a wildcard pattern against the available nuclides
+///
+/// Patterns are split into the element and mass number, which are matched
+/// against the symbol and mass number separately. So "Cs*" is every caesium
+/// isotope, "C*" is only carbon, and "*137" is every nuclide with A = 137.
+/// Only ground states are returned, as for elements.
+fn expand_wildcard(pattern: &str, cli: &Cli) -> Result<Vec<Nuclide>> {
+    let pattern = pattern.to_lowercase().replace('-', "");
+
+    let mut f = load_available(cli)?
+        .into_iter()
+        .filter(|n| !matches!(n.state, IsomerState::Excited(_)))
+        .filter(|n| wildcard_matches(&pattern, n))
+        .collect::<Vec<Nuclide>>();
+    f.sort_by_key(|n| n.name());
+    f.dedup();
+
+    if f.is_empty() {
+        warn!("No nuclides match \"{pattern}\"");
+    }
+
+    debug!("Expanded \"{pattern}\" into {} nuclides", f.len());
+    Ok(f)
+}
+
+/// Check for a wildcard pattern rather than the "co60*" isomer notation
+///
+/// A single trailing `*` after a mass number is the first excited state, so is
+/// left to the usual nuclide parsing.
+fn is_wildcard(input: &str) -> bool {
+    let is_isomer = input
+        .strip_suffix('*')
+        .is_some_and(|s| s.ends_with(|c: char| c.is_ascii_digit()) && !is_wildcard_text(s));
+    is_wildcard_text(input) && !is_isomer
+}
+
+/// Check for any `*` or `?` wildcard characters
+fn is_wildcard_text(input: &str) -> bool {
+    input.contains(['*', '?'])
+}
+
+/// Split a lowercase pattern into the element and mass number parts
+///
+/// The element is the leading letters, or a leading `*` for any element, and
+/// everything after is the mass number. A missing mass number is any.
+fn split_pattern(pattern: &str) -> (&str, &str) {
+    let split = match pattern.starts_with('*') {
+        true => 1,
+        false => pattern
+            .find(|c: char| !c.is_ascii_alphabetic() && c != '?')
+            .unwrap_or(pattern.len()),
+    };
+
+    match pattern.split_at(split) {
+        (element, "") => (element, "*"),
+        parts => parts,
+    }
+}
+
+/// Check if a nuclide matches a lowercase wildcard pattern
+fn wildcard_matches(pattern: &str, nuclide: &Nuclide) -> bool {
+    let (element, mass) = split_pattern(pattern);
+    wildcard_match(element, &nuclide.symbol.to_lowercase())
+        && wildcard_match(mass, &nuclide.isotope.to_string())
+}
+
+/// Simple glob matching where `*` is any number of characters and `?` is one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut rest = pattern.chars();
+    let mut chars = text.chars();
+    match rest.next() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len())
+            .filter(|i| text.is_char_boundary(*i))
+            .any(|i| wildcard_match(rest.as_str(), &text[i..])),
+        Some('?') => chars.next().is_some() && wildcard_match(rest.as_str(), chars.as_str()),
+        Some(c) => chars.next() == Some(c) && wildcard_match(rest.as_str(), chars.as_str()),
+    }
+}
+
+/// Sorted names of every nuclide with data for the requested radiation types
+pub fn available_names(cli: &Cli) -> Result<Vec<String>> {
+    let mut names = load_available(cli)?
+        .iter()
+        .map(|n| n.name_with_state())
+        .collect::<Vec<String>>();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// All nuclides with data for the requested radiation types
+fn load_available(cli: &Cli) -> Result<Vec<Nuclide>> {
     let available = match cli.fetch {
-        false => iaea::load_available(cli.rad.into())?,
-        true => iaea::fetch_available()?,
+        false => {
+            let mut available = Vec::new();
+            for (_, rad_type) in CliRadType::iaea_types(&cli.rad) {
+                available.extend(iaea::load_available(rad_type)?);
+            }
+            available
+        }
+        true => net::fetch_available()?,
     };
+    Ok(available)
+}
+
+/// Expand elements into their ground state nuclides
+fn expand_elements(nuclide: Nuclide, cli: &Cli) -> Result<Vec<Nuclide>> {
+    // ok to do in a loop, this is in a oncecell and only ever loaded once
+    let available = load_available(cli)?;
 
     if nuclide.isotope != 0 {
         return Ok(vec![nuclide]);
     };
 
-    // todo this should expand to all excited states too?
-    debug!(
-        "Expanding {} element into ground state isotopes",
-        nuclide.symbol
-    );
+    debug!("Expanding {} element into ground state isotopes", nuclide.symbol);
+
+    // excited states come from the records later, where they may be filtered
     let f: Vec<Nuclide> = available
         .into_iter()
         .filter(|n| n.symbol == nuclide.symbol)
+        .filter(|n| !matches!(n.state, IsomerState::Excited(_)))
         .collect();
 
     trace!(
@@ -84,12 +560,227 @@ fn expand_elements(nuclide: Nuclide, cli: &Cli) -> Result<Vec<Nuclide>> {
     Ok(f)
 }
 
+/// Find every excited state of a nuclide present in the decay records
+///
+/// States are counted from the unique parent energies, following the same
+/// assumptions as [NuclideData::find_records] when no ground state exists.
+/// States with a known half-life below `--isomer-halflife-min` are skipped.
+fn excited_states(nuclide: &Nuclide, cli: &Cli) -> Vec<Nuclide> {
+    let mut parent_states = CliRadType::iaea_types(&cli.rad)
+        .into_iter()
+        .filter_map(|(_, r)| nuclide_records(nuclide, r, cli.fetch))
+        .flat_map(|(records, _)| records)
+        .filter_map(|r| r.p_energy.map(|e| (e, r.half_life)))
+        .collect::<Vec<(f32, Option<f32>)>>();
+    parent_states.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    parent_states.dedup_by(|a, b| a.0 == b.0);
+
+    // the ground state is not counted if present
+    if parent_states.first().is_some_and(|(e, _)| *e == 0.0) {
+        parent_states.remove(0);
+    }
+
+    parent_states
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, half_life))| match (cli.isomer_halflife_min, half_life) {
+            (Some(min), Some(t)) => *t >= min,
+            _ => true,
+        })
+        .map(|(i, _)| {
+            let mut isomer = nuclide.clone();
+            isomer.state = IsomerState::Excited((i + 1) as _);
+            isomer
+        })
+        .collect()
+}
+
+/// Load or fetch all records for a nuclide and radiation type, and their source
+fn nuclide_records(
+    nuclide: &Nuclide,
+    radtype: iaea::RadType,
+    fetch: bool,
+) -> Option<(RecordSet, DataSource)> {
+    match fetch {
+        false => iaea::load_nuclide(nuclide.clone(), radtype).map(|r| (r, DataSource::Local)),
+        true => net::fetch_nuclide(nuclide, radtype),
+    }
+}
+
+/// Follow the decay chain of a nuclide to include all progeny
+///
+/// Daughters are taken from the decay records of every radiation type, and the
+/// chain is followed until nothing further decays or `--chain-depth` is
+/// reached. Each nuclide is only included once, which also protects against
+/// cyclic chains.
+///
+/// Every nuclide is paired with the cumulative branching fraction from the
+/// requested parent. Daughters reached by more than one branch in the same
+/// generation have the fractions summed, while any later branch to a nuclide
+/// already in the chain is ignored.
+pub fn expand_chain(nuclide: &Nuclide, cli: &Cli) -> Vec<(Nuclide, f32)> {
+    debug!("Expanding decay chain of {}", nuclide.name_with_state());
+    let mut chain = vec![(nuclide.clone(), 1.0)];
+    let mut generation = vec![(nuclide.clone(), 1.0)];
+
+    for _ in 0..cli.chain_depth {
+        let mut next: Vec<(Nuclide, f32)> = Vec::new();
+        for (parent, parent_fraction) in &generation {
+            for (daughter, fraction) in daughters(parent, cli.fetch) {
+                let fraction = parent_fraction * fraction;
+                trace!(
+                    "{} -> {} ({fraction:.4e})",
+                    parent.name_with_state(),
+                    daughter.name_with_state()
+                );
+
+                if let Some(entry) = next.iter_mut().find(|(n, _)| *n == daughter) {
+                    entry.1 += fraction;
+                } else if chain.iter().any(|(n, _)| *n == daughter) {
+                    debug!(
+                        "{} already in the chain, ignoring branch from {}",
+                        daughter.name_with_state(),
+                        parent.name_with_state()
+                    );
+                } else {
+                    next.push((daughter, fraction));
+                }
+            }
+        }
+        chain.extend(next.iter().cloned());
+        generation = next;
+
+        if generation.is_empty() {
+            break;
+        }
+    }
+
+    if !generation.is_empty() {
+        warn!(
+            "Decay chain of {} truncated at a depth of {}",
+            nuclide.name_with_state(),
+            cli.chain_depth
+        );
+    }
+
+    chain
+}
+
+/// Find the unique daughters of a nuclide and their branching fractions
+///
+/// Branching ratios are taken from the decay records, and assumed to be 100%
+/// if unknown.
+fn daughters(parent: &Nuclide, fetch: bool) -> Vec<(Nuclide, f32)> {
+    let data = NuclideData::new(parent.clone());
+
+    let mut branches = CliRadType::iaea_types(&CliRadType::TYPES)
+        .into_iter()
+        .flat_map(|(_, r)| data.type_records(r, fetch, false))
+        .map(|r| (r.daughter_name(), r.branching))
+        .collect::<Vec<(String, Option<f32>)>>();
+    branches.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // the same branch appears on every record, so take the largest of each
+    let mut unique: Vec<(String, Option<f32>)> = Vec::new();
+    for (name, branching) in branches {
+        match unique.last_mut() {
+            Some(last) if last.0 == name => {
+                last.1 = match (last.1, branching) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    (a, b) => a.or(b),
+                }
+            }
+            _ => unique.push((name, branching)),
+        }
+    }
+
+    unique
+        .iter()
+        .filter_map(|(name, branching)| {
+            let fraction = branching.map_or(1.0, |br| br / 100.0);
+            Nuclide::try_from(name).ok().map(|n| (n, fraction))
+        })
+        .filter(|(n, _)| n != parent)
+        .collect()
+}
+
 /// Basic data structure for collecting only the relevant nuclide records
 #[derive(Debug, Clone)]
 pub struct NuclideData {
     pub name: String,
     pub nuclide: iaea::Nuclide,
-    pub records: RecordSet,
+    pub records: Vec<TaggedRecord>,
+    /// Source activity in Bq, if emission rates are requested
+    pub activity: Option<f64>,
+    /// Units of reported intensities, records are always stored in percent
+    pub units: IntensityUnits,
+    /// Units of reported energies, records are always stored in keV
+    pub energy_unit: EnergyUnit,
+    /// Cumulative branching fraction from the requested parent of a chain
+    pub chain_fraction: f32,
+    /// Every record of every parent energy before any filtering, if requested
+    pub all_records: Vec<TaggedRecord>,
+    /// Include the source of every record in the serialised output
+    pub show_source: bool,
+    /// Include the nuclide metadata in the outputs
+    pub with_metadata: bool,
+}
+
+/// Physical properties of a nuclide for self-describing outputs
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Metadata {
+    /// Atomic number
+    pub z: Option<u32>,
+    /// Mass number
+    pub a: u32,
+    /// Isomer state index, 0 for the ground state
+    pub state: u32,
+    /// Half-life in seconds
+    pub half_life: Option<f32>,
+}
+
+impl std::fmt::Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let z = self.z.map_or("unknown".to_string(), |z| z.to_string());
+        let half_life = self
+            .half_life
+            .map_or("unknown".to_string(), |t| format!("{t:e} s"));
+        write!(
+            f,
+            "Z = {z}, A = {}, state = {}, half-life = {half_life}",
+            self.a, self.state
+        )
+    }
+}
+
+/// A decay record labelled with the radiation type it was retrieved for
+#[derive(Debug, Clone)]
+pub struct TaggedRecord {
+    pub rad_type: CliRadType,
+    /// Whether the record was pre-fetched or fetched from the IAEA
+    pub source: DataSource,
+    pub record: Record,
+}
+
+impl Deref for TaggedRecord {
+    type Target = Record;
+
+    fn deref(&self) -> &Self::Target {
+        &self.record
+    }
+}
+
+impl DerefMut for TaggedRecord {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.record
+    }
+}
+
+impl TaggedRecord {
+    /// Emission rate of this line in particles/s for a given activity in Bq
+    pub fn emission_rate(&self, activity: f64) -> Option<f64> {
+        self.intensity.map(|i| i as f64 / 100.0 * activity)
+    }
 }
 
 /// Custom serialisation of nuclide data
@@ -99,20 +790,151 @@ impl Serialize for NuclideData {
         S: Serializer,
     {
         // Create a struct serializer
-        let mut state = serializer.serialize_struct("Nuclide", 3)?;
+        let n_fields = 5
+            + usize::from(self.activity.is_some())
+            + usize::from(self.show_source)
+            + usize::from(self.with_metadata)
+            + usize::from(!self.all_records.is_empty());
+        let mut state = serializer.serialize_struct("Nuclide", n_fields)?;
 
         state.serialize_field("name", &self.name)?;
 
-        let energy: Vec<Option<f32>> = self.records.iter().map(|r| r.energy).collect();
-        let intensity: Vec<Option<f32>> = self.records.iter().map(|r| r.intensity).collect();
+        if self.with_metadata {
+            state.serialize_field("metadata", &self.metadata())?;
+        }
+
+        let energy: Vec<Option<f32>> =
+            self.records.iter().map(|r| self.in_energy_unit(r.energy)).collect();
+        let intensity: Vec<Option<f32>> =
+            self.records.iter().map(|r| self.in_units(r.intensity)).collect();
+        let radiation: Vec<&str> = self.records.iter().map(|r| r.rad_type.name()).collect();
+        let parent_energy: Vec<Option<f32>> =
+            self.records.iter().map(|r| self.in_energy_unit(r.p_energy)).collect();
 
         state.serialize_field("energy", &energy)?;
         state.serialize_field("intensity", &intensity)?;
+        state.serialize_field("radiation", &radiation)?;
+        state.serialize_field("parent_energy", &parent_energy)?;
+
+        if let Some(activity) = self.activity {
+            state.serialize_field("emission_rate", &self.emission_rates(activity))?;
+        }
+
+        if self.show_source {
+            let source: Vec<&str> = self.records.iter().map(|r| r.source.name()).collect();
+            state.serialize_field("source", &source)?;
+        }
+
+        // unaltered, so always in keV and percent
+        if !self.all_records.is_empty() {
+            let raw: Vec<RawRecord> = self.all_records.iter().map(RawRecord::from).collect();
+            state.serialize_field("raw_records", &raw)?;
+        }
 
         state.end()
     }
 }
 
+/// A single unfiltered record, serialised as one object per record
+#[derive(serde::Serialize)]
+struct RawRecord<'a> {
+    radiation: &'a str,
+    energy: Option<f32>,
+    intensity: Option<f32>,
+    parent_energy: Option<f32>,
+}
+
+impl<'a> From<&'a TaggedRecord> for RawRecord<'a> {
+    fn from(record: &'a TaggedRecord) -> Self {
+        Self {
+            radiation: record.rad_type.name(),
+            energy: record.energy,
+            intensity: record.intensity,
+            parent_energy: record.p_energy,
+        }
+    }
+}
+
+/// Intermediate representation of previously serialised nuclide data
+#[derive(serde::Deserialize)]
+struct SerialisedNuclide {
+    name: String,
+    energy: Vec<Option<f32>>,
+    intensity: Vec<Option<f32>>,
+    #[serde(default)]
+    radiation: Vec<String>,
+    #[serde(default)]
+    parent_energy: Vec<Option<f32>>,
+    #[serde(default)]
+    source: Vec<String>,
+}
+
+/// Custom deserialisation of nuclide data
+///
+/// Records are rebuilt from the parallel energy, intensity, radiation, parent
+/// energy, and source arrays. Anything not serialised (e.g. half-life) is left
+/// empty, and names that are not a single nuclide (e.g. "mixture") are kept
+/// as they are with no atomic or mass number.
+impl<'de> Deserialize<'de> for NuclideData {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = SerialisedNuclide::deserialize(deserializer)?;
+
+        let nuclide = Nuclide::try_from(&data.name).unwrap_or_else(|_| Nuclide {
+            symbol: data.name.clone(),
+            isotope: 0,
+            state: IsomerState::Ground,
+        });
+
+        if data.energy.len() != data.intensity.len() {
+            return Err(de::Error::custom(format!(
+                "Mismatched energy and intensity lengths for {}",
+                data.name
+            )));
+        }
+
+        let records = data
+            .energy
+            .into_iter()
+            .zip(data.intensity)
+            .enumerate()
+            .map(|(i, (energy, intensity))| TaggedRecord {
+                rad_type: data
+                    .radiation
+                    .get(i)
+                    .and_then(|r| CliRadType::from_name(r))
+                    .unwrap_or_default(),
+                source: data
+                    .source
+                    .get(i)
+                    .and_then(|s| DataSource::from_name(s))
+                    .unwrap_or_default(),
+                record: Record {
+                    energy,
+                    intensity,
+                    p_energy: data.parent_energy.get(i).copied().flatten(),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Ok(NuclideData {
+            name: data.name,
+            nuclide,
+            records,
+            activity: None,
+            units: IntensityUnits::default(),
+            energy_unit: EnergyUnit::default(),
+            chain_fraction: 1.0,
+            all_records: Vec::new(),
+            show_source: false,
+            with_metadata: false,
+        })
+    }
+}
+
 impl PartialEq for NuclideData {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name && self.nuclide == other.nuclide
@@ -120,6 +942,45 @@ impl PartialEq for NuclideData {
 }
 
 impl NuclideData {
+    /// Create an entry for a nuclide with no records yet
+    pub fn new(nuclide: Nuclide) -> Self {
+        Self {
+            name: nuclide.name_with_state(),
+            nuclide,
+            records: Vec::new(),
+            activity: None,
+            units: IntensityUnits::default(),
+            energy_unit: EnergyUnit::default(),
+            chain_fraction: 1.0,
+            all_records: Vec::new(),
+            show_source: false,
+            with_metadata: false,
+        }
+    }
+
+    /// Atomic number, mass number, isomer state, and half-life of the nuclide
+    ///
+    /// The half-life is taken from the first record that gives one.
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            z: aliases::atomic_number(&self.nuclide.symbol),
+            a: self.nuclide.isotope as u32,
+            state: match self.nuclide.state {
+                IsomerState::Excited(i) => i as u32,
+                _ => 0,
+            },
+            half_life: self.records.iter().find_map(|r| r.half_life),
+        }
+    }
+
+    /// Records with both an energy and an intensity, i.e. usable source lines
+    pub fn valid_lines(&self) -> Vec<&TaggedRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.energy.is_some() && record.intensity.is_some())
+            .collect()
+    }
+
     /// Normalisation factor for the decay data
     pub fn norm(&self) -> f64 {
         (self
@@ -129,16 +990,349 @@ impl NuclideData {
             / 100.0) as f64
     }
 
+    /// Warning message if the normalisation deviates from 1 by more than `tol`
+    ///
+    /// The tolerance is a fraction, i.e. 0.05 for intensities summing to
+    /// within 5% of 100%.
+    pub fn norm_warning(&self, tol: f64) -> Option<String> {
+        let norm = self.norm();
+        ((norm - 1.0).abs() > tol).then(|| {
+            format!(
+                "{} intensities sum to {:.2}%, outside of 100 +/- {:.2}%",
+                self.name,
+                norm * 100.0,
+                tol * 100.0
+            )
+        })
+    }
+
+    /// Scale all intensities so that they sum to exactly 100%
+    ///
+    /// Records without an intensity are left as `None`.
+    pub fn renormalize(&mut self) {
+        let norm = self.norm();
+        if norm <= 0.0 {
+            debug!("Unable to renormalise {}, no intensities", self.name);
+            return;
+        }
+
+        debug!("Renormalising {} intensities by 1/{norm}", self.name);
+        for record in self.records.iter_mut() {
+            record.intensity = record.intensity.map(|i| (i as f64 / norm) as f32);
+        }
+    }
+
+    /// Scale all intensities relative to the strongest line at 100%
+    ///
+    /// Records without an intensity are left as `None`.
+    pub fn normalize_to_max(&mut self) {
+        let max = self
+            .records
+            .iter()
+            .filter_map(|r| r.intensity)
+            .fold(0.0, f32::max);
+
+        if max <= 0.0 {
+            debug!("Unable to normalise {} to the strongest line, no intensities", self.name);
+            return;
+        }
+
+        debug!("Normalising {} intensities by 100/{max}", self.name);
+        self.scale_intensities(100.0 / max);
+    }
+
+    /// Emission rates in particles/s for every record given an activity in Bq
+    pub fn emission_rates(&self, activity: f64) -> Vec<Option<f64>> {
+        self.records
+            .iter()
+            .map(|r| r.emission_rate(activity))
+            .collect()
+    }
+
+    /// Scale every record intensity by a constant factor
+    pub fn scale_intensities(&mut self, factor: f32) {
+        for record in self.records.iter_mut() {
+            record.intensity = record.intensity.map(|i| i * factor);
+        }
+    }
+
+    /// Sum record intensities into contiguous energy bins of a fixed width
+    ///
+    /// Returns (bin centre, summed intensity) pairs spanning the lowest to
+    /// highest energy record, including empty bins. Records without an energy
+    /// are excluded and missing intensities contribute nothing. It is an error
+    /// for the records to span more than [MAX_BINS] bins.
+    pub fn rebin(&self, width: f32) -> Result<Vec<(f32, f32)>> {
+        let lines = self
+            .records
+            .iter()
+            .filter_map(|r| r.energy.map(|e| (e, r.intensity.unwrap_or(0.0))))
+            .collect::<Vec<(f32, f32)>>();
+
+        if lines.is_empty() || width <= 0.0 {
+            return Ok(Vec::new());
+        }
+
+        // count the bins before allocating anything, as the width may be tiny
+        let bin = |energy: f32| (energy as f64 / width as f64).floor();
+        let first = lines.iter().map(|(e, _)| bin(*e)).fold(f64::INFINITY, f64::min);
+        let last = lines.iter().map(|(e, _)| bin(*e)).fold(f64::NEG_INFINITY, f64::max);
+        let count = last - first + 1.0;
+
+        if count > MAX_BINS as f64 {
+            bail!(
+                "{} records span {count:.0} bins of --bin-width {width} keV, more than {MAX_BINS}",
+                self.name
+            )
+        }
+
+        let first = first as usize;
+        let mut bins = vec![0.0; count as usize];
+        for (energy, intensity) in lines {
+            bins[bin(energy) as usize - first] += intensity;
+        }
+
+        Ok(bins
+            .into_iter()
+            .enumerate()
+            .map(|(n, intensity)| (((first + n) as f32 + 0.5) * width, intensity))
+            .collect())
+    }
+
+    /// Energies listed more than once for the same radiation type
+    ///
+    /// These may be genuine unresolved doublets or issues with the data, so are
+    /// worth checking. Each duplicated energy is only given once, in keV.
+    pub fn duplicate_energies(&self) -> Vec<f32> {
+        let mut lines = self
+            .records
+            .iter()
+            .filter_map(|r| r.energy.map(|e| (r.rad_type, e)))
+            .collect::<Vec<(CliRadType, f32)>>();
+        lines.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+        let mut duplicates = lines
+            .windows(2)
+            .filter(|pair| pair[0] == pair[1])
+            .map(|pair| pair[0].1)
+            .collect::<Vec<f32>>();
+        duplicates.sort_by(f32::total_cmp);
+        duplicates.dedup();
+        duplicates
+    }
+
+    /// Collapse records of the same radiation type within `tol` keV of each other
+    ///
+    /// Records are sorted by energy, and every line within `tol` of the first
+    /// line of a group is merged into it. Merged lines have the summed
+    /// intensity and the intensity-weighted mean energy, or the plain mean if
+    /// none of the intensities are known. Records with no energy are left
+    /// untouched.
+    pub fn merge_close(&mut self, tol: f32) {
+        let total = self.records.len();
+        let (mut lines, mut untouched): (Vec<TaggedRecord>, Vec<TaggedRecord>) =
+            self.records.drain(..).partition(|r| r.energy.is_some());
+
+        lines.sort_by(|a, b| {
+            a.rad_type
+                .cmp(&b.rad_type)
+                .then_with(|| a.energy.unwrap().total_cmp(&b.energy.unwrap()))
+        });
+
+        let mut groups: Vec<Vec<TaggedRecord>> = Vec::new();
+        for line in lines {
+            match groups.last_mut() {
+                Some(group)
+                    if group[0].rad_type == line.rad_type
+                        && line.energy.unwrap() - group[0].energy.unwrap() <= tol =>
+                {
+                    group.push(line)
+                }
+                _ => groups.push(vec![line]),
+            }
+        }
+
+        for group in groups {
+            let mut merged = group[0].clone();
+
+            if group.len() > 1 {
+                let weights = group.iter().map(|r| r.intensity.unwrap_or(0.0));
+                let total_weight = weights.clone().sum::<f32>();
+                let energies = group.iter().map(|r| r.energy.unwrap());
+
+                merged.energy = Some(match total_weight > 0.0 {
+                    true => energies.zip(weights).map(|(e, w)| e * w).sum::<f32>() / total_weight,
+                    false => energies.sum::<f32>() / group.len() as f32,
+                });
+                merged.intensity = group
+                    .iter()
+                    .filter_map(|r| r.intensity)
+                    .reduce(|a, b| a + b);
+            }
+
+            self.records.push(merged);
+        }
+
+        self.records.append(&mut untouched);
+        debug!(
+            "Merged {} records of {} into {}",
+            total,
+            self.name,
+            self.records.len()
+        );
+    }
+
+    /// Sum record intensities into groups defined by ascending boundaries
+    ///
+    /// Returns the summed intensity of each group, where a record belongs to
+    /// the group including its lower boundary. Records outside the boundaries
+    /// or without an energy are excluded.
+    pub fn group(&self, bounds: &[f32]) -> Vec<f32> {
+        let mut groups = vec![0.0; bounds.len().saturating_sub(1)];
+        for record in &self.records {
+            let (Some(energy), Some(intensity)) = (record.energy, record.intensity) else {
+                continue;
+            };
+
+            if let Some(g) = bounds.windows(2).position(|b| b[0] <= energy && energy < b[1]) {
+                groups[g] += intensity;
+            }
+        }
+        groups
+    }
+
+    /// The most intense record, taking the first in the case of ties
+    pub fn strongest_record(&self) -> Option<&TaggedRecord> {
+        self.records
+            .iter()
+            .filter(|r| r.intensity.is_some())
+            .fold(None, |max: Option<&TaggedRecord>, r| match max {
+                Some(m) if m.intensity >= r.intensity => Some(m),
+                _ => Some(r),
+            })
+    }
+
+    /// Convert an intensity from percent into the reported units
+    pub fn in_units(&self, intensity: Option<f32>) -> Option<f32> {
+        intensity.map(|i| i * self.units.scale())
+    }
+
+    /// Convert an energy from keV into the reported units
+    pub fn in_energy_unit(&self, energy: Option<f32>) -> Option<f32> {
+        energy.map(|e| self.energy_unit.from_kev(e))
+    }
+
+    /// Intensity-weighted mean energy in keV
+    ///
+    /// Only records with both an energy and intensity are included, and `None`
+    /// is returned if there are no such records.
+    pub fn mean_energy(&self) -> Option<f32> {
+        let (weighted, total) = self
+            .records
+            .iter()
+            .filter_map(|r| Some((r.energy?, r.intensity?)))
+            .fold((0.0, 0.0), |(w, t), (e, i)| (w + e * i, t + i));
+
+        match total > 0.0 {
+            true => Some(weighted / total),
+            false => None,
+        }
+    }
+
+    /// Radiation types present in the records, in sorted order
+    pub fn rad_types(&self) -> Vec<CliRadType> {
+        let mut rad_types = self
+            .records
+            .iter()
+            .map(|r| r.rad_type)
+            .collect::<Vec<CliRadType>>();
+        rad_types.sort();
+        rad_types.dedup();
+        rad_types
+    }
+
+    /// Find the relevant records across all requested radiation types
+    ///
+    /// If `explain` is set, the choice of parent energy for each radiation type
+    /// is logged at INFO rather than TRACE level. If `drop_unobserved` is set,
+    /// any records without a quantified intensity are removed. If `keep_all`
+    /// is set, every record is also kept in `all_records` before any of this.
+    pub fn find_records(
+        &mut self,
+        rad_types: &[CliRadType],
+        fetch: bool,
+        explain: bool,
+        drop_unobserved: bool,
+        keep_all: bool,
+    ) {
+        self.records.clear();
+        self.all_records.clear();
+
+        for (rad_type, iaea_type) in CliRadType::iaea_types(rad_types) {
+            let (all, source) = match nuclide_records(&self.nuclide, iaea_type, fetch) {
+                Some((records, source)) => (Some(records), source),
+                None => (None, DataSource::default()),
+            };
+
+            if keep_all {
+                self.all_records
+                    .extend(all.iter().flatten().map(|record| TaggedRecord {
+                        rad_type,
+                        source,
+                        record: record.clone(),
+                    }));
+            }
+
+            let records = self.select_records(iaea_type, all, explain);
+            self.records
+                .extend(records.into_iter().map(|record| TaggedRecord {
+                    rad_type,
+                    source,
+                    record,
+                }));
+        }
+
+        if self.chain_fraction != 1.0 {
+            debug!(
+                "Scaling {} intensities by a branching fraction of {:.4e}",
+                self.name, self.chain_fraction
+            );
+            self.scale_intensities(self.chain_fraction);
+        }
+
+        if drop_unobserved {
+            let total = self.records.len();
+            self.records.retain(|r| r.intensity.is_some());
+            debug!(
+                "Dropped {} unobserved records for {}",
+                total - self.records.len(),
+                self.name
+            );
+        }
+    }
+
     /// Find the relevant records for a particular nuclide and excited state
-    pub fn find_records(&mut self, radtype: iaea::RadType, fetch: bool) {
-        let nuclide_records = match fetch {
-            false => iaea::load_nuclide(self.nuclide.clone(), radtype),
-            true => iaea::fetch_nuclide(self.nuclide.clone(), radtype),
+    fn type_records(&self, radtype: iaea::RadType, fetch: bool, explain: bool) -> RecordSet {
+        let nuclide_records = nuclide_records(&self.nuclide, radtype, fetch);
+        self.select_records(radtype, nuclide_records.map(|(r, _)| r), explain)
+    }
+
+    /// Select the records of the parent energy for this excited state
+    fn select_records(
+        &self,
+        radtype: iaea::RadType,
+        nuclide_records: Option<RecordSet>,
+        explain: bool,
+    ) -> RecordSet {
+        // reasoning behind the parent energy is only shown on request
+        let level = match explain {
+            true => Level::Info,
+            false => Level::Trace,
         };
 
         if nuclide_records.is_none() {
-            trace!("{radtype:?} decay records for {}: 0", self.name,);
-            return;
+            log!(level, "{radtype:?} decay records for {}: 0", self.name);
+            return Vec::new();
         }
 
         if let Some(records) = nuclide_records {
@@ -161,42 +1355,61 @@ impl NuclideData {
 
             let target = if parent_energy[0] == 0.0 {
                 if index >= n {
-                    trace!("No {:?} records for excied state of {}", radtype, self.name);
-                    return;
+                    log!(level, "No {:?} records for excied state of {}", radtype, self.name);
+                    return Vec::new();
                 }
 
+                let state = match index {
+                    0 => "ground state".to_string(),
+                    i => format!("excited state {i}"),
+                };
+                log!(
+                    level,
+                    "Selected {} keV for {} {radtype:?} records, the {state}",
+                    parent_energy[index],
+                    self.name
+                );
                 parent_energy[index]
             } else {
-                trace!(
+                log!(
+                    level,
                     "Note that {} records do not include a ground state",
                     self.nuclide.name()
                 );
 
                 if index == 0 {
-                    trace!(
+                    log!(
+                        level,
                         "No {:?} records for the ground state of {}",
                         radtype,
                         self.name
                     );
-                    return;
+                    return Vec::new();
                 }
 
                 // assume the first record is the first excited state
-                trace!(
+                log!(
+                    level,
                     "Assuming {} keV is the first excited state of {}",
                     parent_energy[0],
                     self.nuclide.name()
                 );
 
                 if index > n {
-                    trace!("No {:?} records for excied state of {}", radtype, self.name);
-                    return;
+                    log!(level, "No {:?} records for excied state of {}", radtype, self.name);
+                    return Vec::new();
                 }
 
+                log!(
+                    level,
+                    "Selected {} keV for {} {radtype:?} records, assumed excited state {index}",
+                    parent_energy[index - 1],
+                    self.name
+                );
                 parent_energy[index - 1]
             };
 
-            self.records = records
+            let records = records
                 .into_iter()
                 .filter(|r| {
                     if let Some(e) = r.p_energy {
@@ -211,30 +1424,119 @@ impl NuclideData {
             trace!(
                 "{radtype:?} decay records for {}: {}",
                 self.name,
-                self.records.len(),
+                records.len(),
             );
+
+            return records;
         }
+
+        Vec::new()
     }
 
     /// Sort records
+    ///
+    /// Ties are broken on a secondary property so that the order is always
+    /// reproducible, regardless of the order the data were provided in.
+    ///
+    /// - Energy ties are sorted by descending intensity
+    /// - Intensity ties are sorted by ascending energy
+    /// - Parent energy ties are sorted by ascending energy
     pub fn sort_records(&mut self, property: &Property) {
+        let compare = |a: Option<f32>, b: Option<f32>| {
+            a.unwrap_or(-1.0).partial_cmp(&b.unwrap_or(-1.0)).unwrap()
+        };
+
         match property {
             Property::Energy => {
                 self.records.sort_by(|a, b| {
-                    a.energy
-                        .unwrap_or(-1.0)
-                        .partial_cmp(&b.energy.unwrap_or(-1.0))
-                        .unwrap()
+                    compare(a.energy, b.energy)
+                        .then_with(|| compare(b.intensity, a.intensity))
                 });
             }
             Property::Intensity => {
                 self.records.sort_by(|a, b| {
-                    b.intensity
-                        .unwrap_or(-1.0)
-                        .partial_cmp(&a.intensity.unwrap_or(-1.0))
-                        .unwrap()
+                    compare(b.intensity, a.intensity)
+                        .then_with(|| compare(a.energy, b.energy))
+                });
+            }
+            Property::ParentEnergy => {
+                self.records.sort_by(|a, b| {
+                    compare(a.p_energy, b.p_energy)
+                        .then_with(|| compare(a.energy, b.energy))
                 });
             }
+            // keep the order the IAEA gave them in
+            Property::None => {}
+        }
+    }
+
+    /// Reverse the current order of the records
+    pub fn reverse_records(&mut self) {
+        self.records.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nuclide(name: &str) -> Nuclide {
+        Nuclide::try_from(&name.to_string()).expect("valid nuclide name")
+    }
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        wildcard_matches(&pattern.to_lowercase(), &nuclide(name))
+    }
+
+    #[test]
+    fn wildcard_any_element_with_mass() {
+        assert!(matches("*37", "ar37"));
+        assert!(matches("*37", "cl37"));
+        assert!(!matches("*37", "cs137"));
+        assert!(matches("*137", "cs137"));
+        assert!(!matches("*137", "cs134"));
+    }
+
+    #[test]
+    fn wildcard_element_is_anchored() {
+        assert!(matches("C*", "c14"));
+        assert!(matches("C*", "c11"));
+        for name in ["co60", "cs137", "cl36", "cr51", "cu64", "cd109"] {
+            assert!(!matches("C*", name), "C* should not match {name}");
         }
+        assert!(matches("Cs*", "cs137"));
+        assert!(!matches("Cs*", "c14"));
+    }
+
+    #[test]
+    fn wildcard_single_character() {
+        assert!(matches("Co5?", "co57"));
+        assert!(matches("Co5?", "co58"));
+        assert!(!matches("Co5?", "co60"));
+        assert!(!matches("Co5?", "co5"));
+        assert!(!matches("Co5?", "cs57"));
+    }
+
+    #[test]
+    fn spellings_of_one_nuclide_are_deduplicated() {
+        let inputs = ["co60", "Co60", "CO60", "Co60m0", "cobalt60", "co60g"].map(String::from);
+        let mut data = canonical_inputs(&inputs)
+            .iter()
+            .map(|n| canonical_state(nuclide(n)))
+            .map(NuclideData::new)
+            .collect::<Vec<NuclideData>>();
+
+        dedup_nuclides(&mut data);
+
+        assert_eq!(data.len(), 1, "expected one entry, found {data:?}");
+        assert_eq!(data[0].nuclide, canonical_state(nuclide("Co60")));
+    }
+
+    #[test]
+    fn strict_reports_every_unknown_nuclide() {
+        let args = ["ddata", "co06", "fe99", "--strict"].map(String::from);
+        let cli = crate::cli::parse_from(&args).unwrap();
+        let error = parse_nuclides(&cli, None).unwrap_err().to_string();
+        assert!(error.contains("co06") && error.contains("fe99"), "{error}");
     }
 }
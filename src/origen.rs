@@ -0,0 +1,103 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::mcnp::wrap_text;
+use crate::nuclide::NuclideData;
+use crate::wrappers::GroupStructure;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use anyhow::Result;
+use log::warn;
+
+const KEV_TO_MEV: f32 = 1.0e-03;
+
+/// File extension for ORIGEN photon spectra
+pub const EXTENSION: &str = "origen";
+
+/// ORIGEN 18-group gamma boundaries in keV, ascending
+static ORIGEN_18: [f32; 19] = [
+    0.0, 20.0, 30.0, 45.0, 70.0, 100.0, 150.0, 300.0, 450.0, 700.0, 1000.0, 1500.0, 2000.0,
+    2500.0, 3000.0, 4000.0, 6000.0, 8000.0, 14000.0,
+];
+
+/// Energy group boundaries in keV for a group structure, ascending
+fn boundaries(structure: GroupStructure) -> &'static [f32] {
+    match structure {
+        GroupStructure::Origen18 => &ORIGEN_18,
+    }
+}
+
+/// Writes the ORIGEN photon spectra to a file at the specified path.
+///
+/// Records are summed into the energy groups of the chosen structure, with
+/// groups listed from highest to lowest energy as ORIGEN expects.
+pub fn write(
+    nuclides: &[NuclideData],
+    structure: GroupStructure,
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "origen.origen", file)?;
+    let spectra = nuclides
+        .iter()
+        .map(|n| nuclide_spectrum(n, structure))
+        .collect::<Vec<String>>()
+        .join("");
+    f.write_all(spectra.as_bytes())?;
+    f.finish()
+}
+
+/// Make a single group-wise photon spectrum for a nuclide
+fn nuclide_spectrum(nuclide: &NuclideData, structure: GroupStructure) -> String {
+    let bounds = boundaries(structure);
+    let groups = nuclide.group(bounds);
+
+    let total = groups.iter().sum::<f32>();
+    if total <= 0.0 {
+        return f!("' {} records contained no valid decay data\n", nuclide.name);
+    }
+
+    // anything above the highest boundary or without an energy is lost
+    if (total * 1e-2) < nuclide.norm() as f32 * 0.999 {
+        warn!(
+            "Some {} records are missing an energy or outside the {structure} groups",
+            nuclide.name
+        );
+    }
+
+    // Create comment lines with nuclide name and normalization factor
+    let comment = f!(
+        "' {} decay data, norm = {} particles/decay\n' {} groups, {}",
+        nuclide.name,
+        (total as f64 * 1e-2).sci(5, 2),
+        structure,
+        "boundaries in MeV from high to low"
+    );
+
+    let bounds = bounds
+        .iter()
+        .rev()
+        .map(|b| (b * KEV_TO_MEV).sci(5, 2))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let intensities = groups
+        .iter()
+        .rev()
+        .map(|i| (i * 1e-2).sci(5, 2))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    f!(
+        "{}\n{}\n{}\n\n",
+        comment,
+        wrap_text(f!("bounds=[ {bounds} ]"), 80, "    "),
+        wrap_text(f!("intensity=[ {intensities} ]"), 80, "    ")
+    )
+}
@@ -0,0 +1,115 @@
+// internal
+use crate::nuclide::NuclideData;
+use crate::table::DEFAULT_WIDTH;
+
+// standard lib
+use std::io::Write;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use colored::*;
+
+/// Number of rows used for the bars of every plot
+pub const HEIGHT: usize = 12;
+
+/// Block characters for each eighth of a row, from empty to full
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Prints a spectrum plot of every nuclide to the standard output.
+///
+/// Plots fill `width`, which defaults to the terminal width or 80 columns if
+/// not printing to a terminal.
+pub fn print(nuclides: &[NuclideData], no_colour: bool, width: Option<usize>) {
+    let width = width.unwrap_or_else(|| {
+        terminal_size::terminal_size().map_or(DEFAULT_WIDTH, |(w, _)| w.0 as usize)
+    });
+
+    let text = nuclides
+        .iter()
+        .map(|n| render(n, width, HEIGHT))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    if no_colour {
+        std::io::stdout()
+            .write_all(&strip_ansi_escapes::strip(&text))
+            .expect("Unable to remove ansi colour escapes");
+    } else {
+        print!("{text}")
+    }
+}
+
+/// Renders a histogram of intensity against energy for a single nuclide.
+///
+/// Intensities are summed into one bin per column between the lowest and
+/// highest energy, and each bar is drawn to the nearest eighth of a row
+/// relative to the largest bin. Records without an energy or intensity are
+/// left out.
+pub fn render(nuclide: &NuclideData, width: usize, height: usize) -> String {
+    let records = nuclide
+        .records
+        .iter()
+        .filter_map(|r| r.energy.zip(r.intensity))
+        .collect::<Vec<(f32, f32)>>();
+
+    if records.is_empty() {
+        return f!("\n {} has no lines to plot\n", nuclide.name);
+    }
+
+    // leave a margin either side of the bars
+    let columns = width.saturating_sub(2).max(1);
+    let height = height.max(1);
+
+    let min = records.iter().map(|(e, _)| *e).fold(f32::INFINITY, f32::min);
+    let max = records.iter().map(|(e, _)| *e).fold(f32::NEG_INFINITY, f32::max);
+    let span = max - min;
+
+    let mut bins = vec![0.0; columns];
+    for (energy, intensity) in &records {
+        let column = match span > 0.0 {
+            true => ((energy - min) / span * (columns - 1) as f32).round() as usize,
+            false => 0,
+        };
+        bins[column.min(columns - 1)] += intensity;
+    }
+
+    let largest = bins.iter().copied().fold(0.0, f32::max);
+    let eighths = bins
+        .iter()
+        .map(|b| match largest > 0.0 {
+            true => (b / largest * (height * 8) as f32).round() as usize,
+            false => 0,
+        })
+        .collect::<Vec<usize>>();
+
+    let mut plot = f!(
+        "\n {} [max bin {} {}]\n",
+        nuclide.name,
+        nuclide.in_units(Some(largest)).unwrap_or_default().sci(3, 2),
+        nuclide.units.symbol()
+    )
+    .bold()
+    .to_string();
+
+    for row in (0..height).rev() {
+        let bars = eighths
+            .iter()
+            .map(|e| BLOCKS[e.saturating_sub(row * 8).min(8)])
+            .collect::<String>();
+        plot += &f!(" {}\n", bars.trim_end().yellow());
+    }
+
+    // energy axis with the range at either end
+    let unit = nuclide.energy_unit;
+    let low = f!("{} {unit}", unit.from_kev(min));
+    let high = f!("{} {unit}", unit.from_kev(max));
+    plot += &f!(" {}\n", "─".repeat(columns));
+    plot += &f!(
+        " {low}{high:>pad$}\n",
+        pad = columns.saturating_sub(low.chars().count())
+    );
+
+    plot
+}
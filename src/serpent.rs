@@ -0,0 +1,143 @@
+// internal
+use crate::cli::Cli;
+use crate::{create_file_with_fallback, FileOptions};
+use crate::mcnp::wrap_text;
+use crate::nuclide::{NuclideData, TaggedRecord};
+use crate::wrappers::CliRadType;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use anyhow::{bail, Result};
+
+const KEV_TO_MEV: f32 = 1.0e-03;
+
+/// File extension for Serpent source definitions
+pub const EXTENSION: &str = "serpent";
+
+/// Options controlling how the Serpent sources are written
+#[derive(Debug, Clone)]
+pub struct SerpentOptions {
+    /// Number of significant figures for all values
+    pub precision: usize,
+    /// Maximum line width of the source cards
+    pub width: usize,
+}
+
+impl From<&Cli> for SerpentOptions {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            precision: cli.precision.into(),
+            width: cli.mcnp_width,
+        }
+    }
+}
+
+/// Writes the Serpent source definitions to a file at the specified path.
+///
+/// Values use the same precision and line width as the MCNP cards.
+pub fn write(
+    nuclides: &[NuclideData],
+    options: &SerpentOptions,
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let cards = generate_serpent_cards(nuclides, options)?;
+    let mut f = create_file_with_fallback(path, EXTENSION, "serpent.txt", file)?;
+    f.write_all(cards.as_bytes())?;
+    f.finish()
+}
+
+/// Make source definitions for every nuclide
+fn generate_serpent_cards(nuclides: &[NuclideData], options: &SerpentOptions) -> Result<String> {
+    Ok(nuclides
+        .iter()
+        .map(|n| nuclide_source(n, options))
+        .collect::<Result<Vec<String>>>()?
+        .join(""))
+}
+
+/// Make the source definitions for a nuclide
+///
+/// Serpent needs a particle per source, so a separate source is written for
+/// each radiation type, named `<nuclide>_<type>`. Serpent has no alpha source
+/// particle, so it is an error to write sources for any alpha records.
+fn nuclide_source(nuclide: &NuclideData, options: &SerpentOptions) -> Result<String> {
+    let precision = options.precision;
+    let filtered_records = nuclide.valid_lines();
+
+    if filtered_records.is_empty() {
+        return Ok(f!("% {} records contained no valid decay data\n", nuclide.name));
+    }
+
+    let mut sources = String::new();
+    for rad_type in nuclide.rad_types() {
+        let records = filtered_records
+            .iter()
+            .filter(|r| r.rad_type == rad_type)
+            .collect::<Vec<&&TaggedRecord>>();
+
+        if records.is_empty() {
+            continue;
+        }
+
+        let particle = particle(rad_type, nuclide)?;
+
+        let norm = records
+            .iter()
+            .fold(0.0, |acc, r| acc + r.intensity.unwrap() as f64 * 1e-2);
+
+        // Create a comment line with nuclide name and normalization factor
+        let comment = f!(
+            "% {} {} decay data, norm = {} particles/decay",
+            nuclide.name,
+            rad_type,
+            norm.sci(precision, 2)
+        );
+
+        // Discrete energy (MeV) and weight pairs for the source spectrum
+        let sb_card = f!(
+            "sb {} 1 {}",
+            records.len(),
+            records
+                .iter()
+                .map(|record| {
+                    f!(
+                        "{} {}",
+                        (record.energy.unwrap() * KEV_TO_MEV).sci(precision, 2),
+                        (record.intensity.unwrap() * 1e-2).sci(precision, 2)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        );
+
+        sources += &f!(
+            "{}\nsrc {}_{} {}\n{}\n\n",
+            comment,
+            nuclide.name,
+            rad_type.name().replace([' ', '-'], ""),
+            particle,
+            wrap_text(sb_card, options.width, "    ")
+        );
+    }
+
+    Ok(sources)
+}
+
+/// Serpent source particle for the radiation type
+fn particle(rad_type: CliRadType, nuclide: &NuclideData) -> Result<&'static str> {
+    match rad_type {
+        CliRadType::BetaPlus => Ok("p"),
+        CliRadType::BetaMinus | CliRadType::Electron => Ok("e"),
+        CliRadType::Gamma | CliRadType::Xray => Ok("g"),
+        CliRadType::Alpha | CliRadType::All => {
+            bail!("Serpent has no {rad_type} source particle for {}", nuclide.name)
+        }
+    }
+}
@@ -1,13 +1,15 @@
 // internal
-use crate::create_file_with_fallback;
-use crate::nuclide::NuclideData;
+use crate::cli::Cli;
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::{NuclideData, TaggedRecord};
+use crate::wrappers::{CliRadType, Column, EnergyUnit, IntensityUnits, TableTheme};
 
 // standard lib
 use std::io::Write;
 use std::path::Path;
+use std::ptr;
 
 // neutronics toolbox
-use ntools::iaea::Record;
 use ntools::utils::{OptionExt, ValueExt};
 
 // other
@@ -15,8 +17,137 @@ use anyhow::Result;
 use colored::*;
 use log::warn;
 
+/// File extension for the plain text table
+pub const EXTENSION: &str = "txt";
+
+/// File extension for the normalisation report
+pub const NORM_EXTENSION: &str = "norm";
+
+/// Options controlling the layout of the table
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    /// Columns to include, always displayed in the standard order
+    pub columns: Vec<Column>,
+    /// Colours used for each part of the table
+    pub theme: Theme,
+    /// Emphasise the most intense record of each nuclide
+    pub highlight: bool,
+    /// Colour intensities relative to the most intense record of each nuclide
+    pub heatmap: bool,
+    /// Include the source of every record
+    pub show_source: bool,
+    /// End every nuclide with the number of lines and total intensity
+    pub footer: bool,
+    /// Units of the intensity column
+    pub units: IntensityUnits,
+    /// Units of the energy column
+    pub energy_unit: EnergyUnit,
+}
+
+impl From<&Cli> for TableOptions {
+    fn from(cli: &Cli) -> Self {
+        let mut columns = cli.columns.clone();
+        columns.sort();
+        columns.dedup();
+        Self {
+            columns,
+            theme: Theme::from(cli.theme),
+            highlight: cli.highlight,
+            heatmap: cli.heatmap,
+            show_source: cli.show_source,
+            footer: !cli.no_footer,
+            units: cli.intensity_units,
+            energy_unit: cli.out_energy_unit,
+        }
+    }
+}
+
+/// Colours for each part of the table, `None` leaves the text uncoloured
+#[derive(Debug, Copy, Clone)]
+pub struct Theme {
+    /// Nuclide headers
+    pub header: Option<Color>,
+    /// Parent and daughter names
+    pub name: Option<Color>,
+    /// Decay modes
+    pub mode: Option<Color>,
+    /// Radiation type labels
+    pub label: Option<Color>,
+    /// Highlighted records
+    pub highlight: Option<Color>,
+}
+
+impl From<TableTheme> for Theme {
+    fn from(theme: TableTheme) -> Self {
+        match theme {
+            TableTheme::Default => Self {
+                header: Some(Color::Magenta),
+                name: Some(Color::Blue),
+                mode: Some(Color::Cyan),
+                label: Some(Color::Cyan),
+                highlight: Some(Color::Yellow),
+            },
+            TableTheme::Mono => Self {
+                header: None,
+                name: None,
+                mode: None,
+                label: None,
+                highlight: None,
+            },
+            TableTheme::HighContrast => Self {
+                header: Some(Color::BrightYellow),
+                name: Some(Color::BrightWhite),
+                mode: Some(Color::BrightCyan),
+                label: Some(Color::BrightGreen),
+                highlight: Some(Color::BrightRed),
+            },
+        }
+    }
+}
+
+/// Apply an optional colour to some text
+fn paint(text: &str, colour: Option<Color>) -> ColoredString {
+    match colour {
+        Some(c) => text.color(c),
+        None => text.normal(),
+    }
+}
+
+/// Number of decades below the strongest line covered by the heatmap
+const HEATMAP_DECADES: f32 = 4.0;
+
+/// Colour on a ramp from dim grey for weak lines to bright yellow
+///
+/// The ramp is logarithmic, so that lines spanning several orders of
+/// magnitude are still distinguishable. Anything more than
+/// [HEATMAP_DECADES] below `max` is the dimmest colour.
+fn heat_colour(intensity: f32, max: f32) -> Color {
+    let fraction = match intensity > 0.0 && max > 0.0 {
+        true => (1.0 + (intensity / max).log10() / HEATMAP_DECADES).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+
+    let ramp = |low: f32, high: f32| (low + (high - low) * fraction) as u8;
+    Color::TrueColor {
+        r: ramp(80.0, 255.0),
+        g: ramp(80.0, 220.0),
+        b: ramp(80.0, 0.0),
+    }
+}
+
+/// Spaces between nuclides laid out side by side
+const COLUMN_GAP: usize = 4;
+
+/// Width assumed when not printing to a terminal
+pub const DEFAULT_WIDTH: usize = 80;
+
 /// Represents a complete table of decay data for nuclides.
-pub struct Table(String);
+pub struct Table {
+    /// Column titles
+    header: String,
+    /// Records of each nuclide
+    blocks: Vec<String>,
+}
 
 impl Table {
     /// Creates a new `Table` from a slice of `NuclideData`.
@@ -24,25 +155,97 @@ impl Table {
     /// # Arguments
     ///
     /// * `nuclides` - A slice of `NuclideData` to be included in the table.
+    /// * `options` - Layout options such as the columns to include.
     ///
     /// # Returns
     ///
     /// A fully generated `Table` as a colourised string.
-    pub fn new(nuclides: &[NuclideData]) -> Self {
-        let mut s = header();
-        s += &content(nuclides);
-        Self(s)
+    pub fn new(nuclides: &[NuclideData], options: &TableOptions) -> Self {
+        let rates = nuclides.iter().any(|n| n.activity.is_some());
+        Self {
+            header: header(options, rates),
+            blocks: content(nuclides, options),
+        }
+    }
+
+    /// Creates a combined `Table` with radiation types side by side.
+    ///
+    /// Rows are the unique energies of each nuclide and there is an intensity
+    /// column for every radiation type present. Missing combinations are left
+    /// blank.
+    ///
+    /// # Arguments
+    ///
+    /// * `nuclides` - A slice of `NuclideData` to be included in the table.
+    /// * `options` - Layout options, only the theme applies.
+    ///
+    /// # Returns
+    ///
+    /// A fully generated `Table` as a colourised string.
+    pub fn new_combined(nuclides: &[NuclideData], options: &TableOptions) -> Self {
+        let mut rad_types = nuclides
+            .iter()
+            .flat_map(|n| n.rad_types())
+            .collect::<Vec<CliRadType>>();
+        rad_types.sort();
+        rad_types.dedup();
+
+        Self {
+            header: combined_header(&rad_types, options),
+            blocks: nuclides
+                .iter()
+                .map(|n| combined_content(n, &rad_types, &options.theme))
+                .collect(),
+        }
     }
 
     /// Prints the table to the standard output.
-    pub fn print(&self, no_colour: bool) {
+    ///
+    /// Nuclides are laid out side by side in as many columns as fit within
+    /// `width`, which defaults to the terminal width or 80 columns if not
+    /// printing to a terminal.
+    pub fn print(&self, no_colour: bool, width: Option<usize>) {
+        let width = width.unwrap_or_else(|| {
+            terminal_size::terminal_size().map_or(DEFAULT_WIDTH, |(w, _)| w.0 as usize)
+        });
+        let text = self.layout(width);
+
         if no_colour {
             std::io::stdout()
-                .write_all(&strip_ansi_escapes::strip(&self.0))
+                .write_all(&strip_ansi_escapes::strip(&text))
                 .expect("Unable to remove ansi colour escapes");
         } else {
-            println!("{}", self.0)
+            println!("{text}")
+        }
+    }
+
+    /// The full table as a single column
+    fn text(&self) -> String {
+        self.header.clone() + &self.blocks.concat()
+    }
+
+    /// Arrange the nuclides side by side in as many columns as fit
+    fn layout(&self, width: usize) -> String {
+        let block_width = self
+            .header
+            .lines()
+            .chain(self.blocks.iter().flat_map(|b| b.lines()))
+            .map(visible_width)
+            .max()
+            .unwrap_or(0);
+
+        let columns = (width + COLUMN_GAP) / (block_width + COLUMN_GAP);
+        if columns <= 1 || self.blocks.len() <= 1 {
+            return self.text();
         }
+
+        let columns = columns.min(self.blocks.len());
+        let mut text = side_by_side(&vec![self.header.as_str(); columns], block_width);
+        for group in self.blocks.chunks(columns) {
+            let group = group.iter().map(|b| b.as_str()).collect::<Vec<&str>>();
+            text += &side_by_side(&group, block_width);
+        }
+        text
     }
 
     /// Writes the table to a file at the specified path.
@@ -50,51 +253,443 @@ impl Table {
     /// # Arguments
     ///
     /// * `path` - The path where the table should be written.
+    /// * `file` - How the output file is opened, e.g. compressed.
     ///
     /// # Returns
     ///
     /// A `Result` indicating success or failure.
-    pub fn write(&self, path: &Path) -> Result<()> {
-        let mut f = create_file_with_fallback(path, "txt", "table.txt")?;
-        f.write_all(&strip_ansi_escapes::strip(&self.0))?;
-        Ok(())
+    pub fn write(&self, path: &Path, file: FileOptions) -> Result<()> {
+        let mut f = create_file_with_fallback(path, EXTENSION, "table.txt", file)?;
+        f.write_all(&strip_ansi_escapes::strip(self.text()))?;
+        f.finish()
+    }
+}
+
+/// Join blocks of text line by line, padding each to the same visible width
+fn side_by_side(blocks: &[&str], width: usize) -> String {
+    let blocks = blocks
+        .iter()
+        .map(|b| b.lines().collect::<Vec<&str>>())
+        .collect::<Vec<Vec<&str>>>();
+    let rows = blocks.iter().map(|b| b.len()).max().unwrap_or(0);
+
+    let mut text = String::new();
+    for row in 0..rows {
+        let mut line = String::new();
+        for block in &blocks {
+            let cell = block.get(row).copied().unwrap_or_default();
+            line += cell;
+            line += &" ".repeat(width - visible_width(cell) + COLUMN_GAP);
+        }
+        text += line.trim_end();
+        text += "\n";
+    }
+    text
+}
+
+/// Width of a line as displayed, ignoring any colour escapes
+fn visible_width(line: &str) -> usize {
+    strip_ansi_escapes::strip_str(line).chars().count()
+}
+
+/// Generates a compact single line summary of a nuclide.
+///
+/// Columns are the name, number of lines, energy of the most intense line in
+/// keV, and the normalisation in particles/decay.
+pub fn summary_line(nuclide: &NuclideData) -> String {
+    format!(
+        "{} {} {} {}",
+        nuclide.name,
+        nuclide.records.len(),
+        nuclide
+            .strongest_record()
+            .and_then(|r| r.energy)
+            .map_or("-".to_string(), |e| e.to_string()),
+        nuclide.norm().sci(5, 2)
+    )
+}
+
+/// Generates a plain `nuclide energy intensity` line for every record.
+///
+/// Records are kept in their current order, and missing values are written
+/// as `-`. There is no header, padding, or colour for easy use with awk.
+pub fn raw_lines(nuclides: &[NuclideData]) -> String {
+    let value = |v: Option<f32>| v.map_or("-".to_string(), |v| v.to_string());
+
+    let mut lines = String::new();
+    for nuclide in nuclides {
+        for record in &nuclide.records {
+            lines.push_str(&format!(
+                "{} {} {}\n",
+                nuclide.name,
+                value(nuclide.in_energy_unit(record.energy)),
+                value(nuclide.in_units(record.intensity))
+            ));
+        }
+    }
+    lines
+}
+
+/// Generates a small table of summary statistics for each nuclide.
+///
+/// Columns are the number of lines, total intensity, and intensity-weighted
+/// mean energy. Nuclides without any valid records are skipped.
+pub fn stats_table(nuclides: &[NuclideData]) -> String {
+    let unit = nuclides.first().map(|n| n.energy_unit).unwrap_or_default();
+    let titles = format!("  Nuclide     Lines   Total [%]     Mean [{unit}]");
+    let width = titles.len() + 1;
+
+    let mut table = String::new();
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+
+    for nuclide in nuclides {
+        let Some(mean) = nuclide.mean_energy() else {
+            continue;
+        };
+        table.push_str(&format!(
+            "  {:<10}  {:<5}   {:<11}   {}\n",
+            nuclide.name,
+            nuclide.records.len(),
+            format_intensity(Some((nuclide.norm() * 100.0) as f32)),
+            format_energy(nuclide.in_energy_unit(Some(mean)))
+        ));
+    }
+
+    table
+}
+
+/// Generates a two column table of the normalisation of each nuclide.
+///
+/// This is the sum of all intensities in particles/decay, i.e. the source
+/// strength without the full list of lines.
+pub fn norm_table(nuclides: &[NuclideData]) -> String {
+    let titles = "  Nuclide     Norm [particles/decay]";
+    let width = titles.len() + 1;
+
+    let mut table = String::new();
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+
+    for nuclide in nuclides {
+        table.push_str(&format!(
+            "  {:<10}  {}\n",
+            nuclide.name,
+            nuclide.norm().sci(5, 2)
+        ));
+    }
+
+    table
+}
+
+/// Writes the normalisation report to a `.norm` file at the specified path.
+pub fn write_norm(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, NORM_EXTENSION, "decay_data.norm", file)?;
+    f.write_all(norm_table(nuclides).as_bytes())?;
+    f.finish()
+}
+
+/// Generates a table of x-ray intensities grouped into shells.
+///
+/// The IAEA records have no shell or transition labels, so lines are grouped
+/// into clusters where each line is within `tol` keV of the next. Every group
+/// is subtotalled, e.g. the K-alpha lines of heavy elements form one group.
+pub fn shell_table(nuclides: &[NuclideData], tol: f32) -> String {
+    let unit = nuclides.first().map(|n| n.energy_unit).unwrap_or_default();
+    let titles = format!(
+        "  Nuclide     Group   {:<20}  Lines   Total [%]",
+        format!("Energy range [{unit}]")
+    );
+    let width = titles.len() + 1;
+
+    let mut table = String::new();
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+
+    for nuclide in nuclides {
+        for (i, group) in shell_groups(nuclide, tol).iter().enumerate() {
+            let (low, high) = (group[0].0, group[group.len() - 1].0);
+            let total = group.iter().filter_map(|line| line.1).sum::<f32>();
+            table.push_str(&format!(
+                "  {:<10}  {:<5}   {:<20}  {:<5}   {}\n",
+                nuclide.name,
+                i + 1,
+                format!(
+                    "{} - {}",
+                    format_energy(nuclide.in_energy_unit(Some(low))),
+                    format_energy(nuclide.in_energy_unit(Some(high)))
+                ),
+                group.len(),
+                format_intensity(nuclide.in_units(Some(total)))
+            ));
+        }
+    }
+
+    table
+}
+
+/// Cluster the x-ray lines of a nuclide by energy proximity
+fn shell_groups(nuclide: &NuclideData, tol: f32) -> Vec<Vec<(f32, Option<f32>)>> {
+    let mut lines = nuclide
+        .records
+        .iter()
+        .filter(|r| r.rad_type == CliRadType::Xray)
+        .filter_map(|r| r.energy.map(|e| (e, r.intensity)))
+        .collect::<Vec<(f32, Option<f32>)>>();
+    lines.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut groups: Vec<Vec<(f32, Option<f32>)>> = Vec::new();
+    for line in lines {
+        match groups.last_mut() {
+            Some(group) if line.0 - group[group.len() - 1].0 <= tol => group.push(line),
+            _ => groups.push(vec![line]),
+        }
     }
+
+    groups
 }
 
-/// Generates the table header.
-fn header() -> String {
+/// Generates a side by side comparison of the lines of two nuclides.
+///
+/// Lines are matched to the closest unmatched line of the other nuclide within
+/// `tol` keV. Lines present in only one nuclide are flagged in the last
+/// column.
+pub fn diff_table(a: &NuclideData, b: &NuclideData, tol: f32) -> String {
+    // energy and intensity of a single line
+    type Line = (f32, Option<f32>);
+
+    let lines = |n: &NuclideData| {
+        let mut lines = n
+            .records
+            .iter()
+            .filter_map(|r| r.energy.map(|e| (e, n.in_units(r.intensity))))
+            .collect::<Vec<Line>>();
+        lines.sort_by(|x, y| x.0.total_cmp(&y.0));
+        lines
+    };
+    let a_lines = lines(a);
+    let mut b_lines = lines(b).into_iter().map(Some).collect::<Vec<Option<Line>>>();
+
+    // pair every line of a with the closest available line of b
+    let mut rows = Vec::new();
+    for a_line in a_lines {
+        let closest = b_lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b_line)| b_line.map(|l| (i, (l.0 - a_line.0).abs())))
+            .filter(|(_, delta)| *delta <= tol)
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+            .map(|(i, _)| i);
+
+        rows.push((Some(a_line), closest.and_then(|i| b_lines[i].take())));
+    }
+    rows.extend(b_lines.into_iter().flatten().map(|b_line| (None, Some(b_line))));
+    rows.sort_by(|x, y| {
+        let energy = |row: &(Option<Line>, Option<Line>)| row.0.or(row.1).map_or(0.0, |l| l.0);
+        energy(x).total_cmp(&energy(y))
+    });
+
+    let titles = format!(
+        "  {:<12} {:<12} {:<12} {:<12} Note",
+        format!("{} [{}]", a.name, a.energy_unit),
+        format!("{} [{}]", a.name, a.units.symbol()),
+        format!("{} [{}]", b.name, b.energy_unit),
+        format!("{} [{}]", b.name, b.units.symbol()),
+    );
+    let width = titles.len() + 1;
+
     let mut table = String::new();
-    table.push_str(&format!("{:-<58}\n", ""));
-    table.push_str(&format!(
-        "  {:^5}   {:^5}  {:^5}   BR    Energy [keV]  Intensity [%]\n",
-        "P", "Mode", "D"
-    ));
-    table.push_str(&format!("{:-<58}\n", ""));
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+
+    for (a_line, b_line) in rows {
+        let energy = |line: Option<Line>| {
+            line.map_or(String::new(), |l| format_energy(a.in_energy_unit(Some(l.0))))
+        };
+        let intensity = |line: Option<Line>| line.map_or(String::new(), |l| format_intensity(l.1));
+        let note = match (a_line, b_line) {
+            (Some(_), None) => format!("only {}", a.name),
+            (None, Some(_)) => format!("only {}", b.name),
+            _ => String::new(),
+        };
+
+        let line = format!(
+            "  {:<12} {:<12} {:<12} {:<12} {}",
+            energy(a_line),
+            intensity(a_line),
+            energy(b_line),
+            intensity(b_line),
+            note
+        );
+        table += line.trim_end();
+        table += "\n";
+    }
+
     table
 }
 
-/// Generates the table content for all nuclide records.
-fn content(nuclides: &[NuclideData]) -> String {
+/// Generates the table header, with an emission rate column if required.
+fn header(options: &TableOptions, rates: bool) -> String {
+    let mut titles = options
+        .columns
+        .iter()
+        .map(|c| column_title(*c, options.units, options.energy_unit))
+        .collect::<Vec<&str>>()
+        .join("");
+
+    if rates {
+        titles += "   Rate [1/s]";
+    }
+
+    if options.show_source {
+        titles += "   Source";
+    }
+
+    let width = titles.len() + 1;
+
+    let mut table = String::new();
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table
+}
+
+/// Generates the combined table header, with a column per radiation type.
+fn combined_header(rad_types: &[CliRadType], options: &TableOptions) -> String {
+    let mut titles = format!("  {:<12}", format!("Energy [{}]", options.energy_unit));
+    for rad_type in rad_types {
+        titles += &format!("   {:<12}", format!("{rad_type} [{}]", options.units.symbol()));
+    }
+    let titles = titles.trim_end();
+
+    let width = titles.len() + 1;
+
     let mut table = String::new();
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table.push_str(&format!("{titles}\n"));
+    table.push_str(&format!("{:-<width$}\n", ""));
+    table
+}
+
+/// Generates the combined rows of energy against radiation type intensities.
+fn combined_content(nuclide: &NuclideData, rad_types: &[CliRadType], theme: &Theme) -> String {
+    // collect the intensity of every radiation type at each unique energy
+    let mut rows: Vec<(f32, Vec<Option<f32>>)> = Vec::new();
+    for record in nuclide.records.iter().filter(|r| r.energy.is_some()) {
+        let energy = record.energy.unwrap();
+        let column = rad_types
+            .iter()
+            .position(|r| *r == record.rad_type)
+            .expect("Radiation type missing from combined columns");
+
+        let index = match rows.iter().position(|(e, _)| *e == energy) {
+            Some(index) => index,
+            None => {
+                rows.push((energy, vec![None; rad_types.len()]));
+                rows.len() - 1
+            }
+        };
+
+        // duplicate lines of the same type are summed
+        let cell = &mut rows[index].1[column];
+        *cell = match (*cell, record.intensity) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+    rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut table = format!("\n {}\n", paint(&nuclide.name, theme.header).bold());
+    for (energy, intensities) in rows {
+        let mut line = format!("  {:<12}", format_energy(nuclide.in_energy_unit(Some(energy))));
+        for intensity in intensities {
+            let cell = nuclide
+                .in_units(intensity)
+                .map_or(String::new(), |i| format_intensity(Some(i)));
+            line += &format!("   {:<12}", cell);
+        }
+        table += line.trim_end();
+        table += "\n";
+    }
+
+    table
+}
+
+/// Column title, padded to the same width as the column values.
+fn column_title(column: Column, units: IntensityUnits, energy_unit: EnergyUnit) -> &'static str {
+    match column {
+        Column::Parent => "    P  ",
+        Column::Mode => "   Mode ",
+        Column::Daughter => "    D   ",
+        Column::Branching => "  BR   ",
+        Column::Energy => match energy_unit {
+            EnergyUnit::Ev => "Energy [eV] ",
+            EnergyUnit::Kev => "Energy [keV]",
+            EnergyUnit::Mev => "Energy [MeV]",
+        },
+        Column::Intensity => match units {
+            IntensityUnits::Percent => "  Intensity [%]",
+            IntensityUnits::Fraction => "  Intensity [-]",
+        },
+    }
+}
+
+/// Generates the table content for all nuclide records, one block per nuclide.
+fn content(nuclides: &[NuclideData], options: &TableOptions) -> Vec<String> {
+    let mut blocks = Vec::new();
     let mut missing_p_erg = false;
 
     for nuclide in nuclides {
+        let mut table = String::new();
         let mut p_energy = -1.0;
-        table += &format_nuclide_header(nuclide, &mut p_energy, &mut missing_p_erg);
+        table += &format_nuclide_header(nuclide, &options.theme, &mut p_energy, &mut missing_p_erg);
 
-        for record in &nuclide.records {
-            table += &format_record(nuclide, record, &mut p_energy, &mut missing_p_erg);
+        let strongest = nuclide.strongest_record();
+        let max_intensity = match options.heatmap {
+            true => strongest.and_then(|r| r.intensity),
+            false => None,
+        };
+
+        // only label the radiation type if there is more than one to choose from
+        let rad_types = nuclide.rad_types();
+        for rad_type in &rad_types {
+            if rad_types.len() > 1 {
+                table += &format_rad_header(*rad_type, &options.theme);
+            }
+
+            for record in nuclide.records.iter().filter(|r| r.rad_type == *rad_type) {
+                let highlight = options.highlight && strongest.is_some_and(|r| ptr::eq(r, record));
+                table += &format_record(
+                    nuclide,
+                    record,
+                    options,
+                    highlight,
+                    max_intensity,
+                    &mut p_energy,
+                    &mut missing_p_erg,
+                );
+            }
+        }
+
+        if options.footer && !nuclide.records.is_empty() {
+            table += &format_footer(nuclide, &options.theme);
         }
 
         missing_p_erg = false;
+        blocks.push(table);
     }
 
-    table
+    blocks
 }
 
 /// Formats the header for a single nuclide.
 fn format_nuclide_header(
     nuclide: &NuclideData,
+    theme: &Theme,
     p_energy: &mut f32,
     missing_p_erg: &mut bool,
 ) -> String {
@@ -116,10 +711,10 @@ fn format_nuclide_header(
             *p_energy = parent_energy;
             header += &format!(
                 "\n {} [E = {} {}, t1/2 = {}]\n",
-                nuclide.name.magenta(),
-                parent_energy.to_string().magenta(),
-                "keV".magenta(),
-                human_readable_halflife(record.half_life).magenta(),
+                paint(&nuclide.name, theme.header),
+                paint(&nuclide.energy_unit.from_kev(parent_energy).to_string(), theme.header),
+                paint(nuclide.energy_unit.name(), theme.header),
+                paint(&human_readable_halflife(record.half_life), theme.header),
             )
             .bold()
             .to_string();
@@ -129,10 +724,30 @@ fn format_nuclide_header(
     header
 }
 
+/// Formats the label for a group of records of one radiation type.
+fn format_rad_header(rad_type: CliRadType, theme: &Theme) -> String {
+    format!("  {}\n", paint(&format!("[{rad_type}]"), theme.label).italic())
+}
+
+/// Formats the line count and total intensity of a nuclide.
+fn format_footer(nuclide: &NuclideData, theme: &Theme) -> String {
+    let total = nuclide.in_units(Some((nuclide.norm() * 100.0) as f32));
+    let footer = format!(
+        "{} lines, total {} [{}]",
+        nuclide.records.len(),
+        format_intensity(total).trim(),
+        nuclide.units.symbol()
+    );
+    format!("  {}\n", paint(&footer, theme.label).italic())
+}
+
 /// Formats a single record for a nuclide.
 fn format_record(
     nuclide: &NuclideData,
-    record: &Record,
+    record: &TaggedRecord,
+    options: &TableOptions,
+    highlight: bool,
+    max_intensity: Option<f32>,
     p_energy: &mut f32,
     missing_p_erg: &mut bool,
 ) -> String {
@@ -154,16 +769,52 @@ fn format_record(
         record_str += "\n";
     }
 
-    record_str += &format!(
-        "  {:<5} > {:^5} > {:<5} {:<6}     {:<7}     {:<7}\n",
-        record.parent_name().blue(),
-        record.decay_mode.display().cyan(),
-        record.daughter_name().blue(),
-        format_branching(record.branching),
-        format_energy(record.energy),
-        format_intensity(record.intensity)
-    )
-    .to_string();
+    let columns = &options.columns;
+    let theme = &options.theme;
+
+    let mut line = String::new();
+    for (i, column) in columns.iter().enumerate() {
+        // decay path arrows only make sense between adjacent path columns
+        let arrow = match i.checked_sub(1).map(|p| columns[p]) {
+            Some(previous) if previous < Column::Branching => " > ",
+            _ => "   ",
+        };
+
+        line += &match column {
+            Column::Parent => format!("  {:<5}", paint(&record.parent_name(), theme.name)),
+            Column::Mode => format!(
+                "{arrow}{:^5}",
+                paint(&record.decay_mode.display(), theme.mode)
+            ),
+            Column::Daughter => format!("{arrow}{:<5}", paint(&record.daughter_name(), theme.name)),
+            Column::Branching => format!(" {:<6}", format_branching(record.branching)),
+            Column::Energy => format!(
+                "     {:<7}",
+                format_energy(nuclide.in_energy_unit(record.energy))
+            ),
+            Column::Intensity => {
+                let intensity = format_intensity(nuclide.in_units(record.intensity));
+                let colour = max_intensity
+                    .zip(record.intensity)
+                    .map(|(max, i)| heat_colour(i, max));
+                format!("     {:<10}", paint(&intensity, colour))
+            }
+        };
+    }
+
+    if let Some(activity) = nuclide.activity {
+        line += &format!("   {:<10}", format_rate(record.emission_rate(activity)));
+    }
+
+    if options.show_source {
+        line += &format!("   {}", record.source);
+    }
+
+    match highlight {
+        true => record_str += &paint(line.trim_end(), theme.highlight).bold().to_string(),
+        false => record_str += line.trim_end(),
+    }
+    record_str += "\n";
 
     record_str
 }
@@ -199,6 +850,14 @@ fn format_intensity(intensity: Option<f32>) -> String {
     }
 }
 
+/// Formats the emission rate value.
+fn format_rate(rate: Option<f64>) -> String {
+    match rate {
+        Some(r) => r.sci(3, 2),
+        None => "  -".to_string(),
+    }
+}
+
 /// Converts an optional half-life value in seconds to a human-readable string.
 fn human_readable_halflife(halflife: Option<f32>) -> String {
     if let Some(seconds) = halflife {
@@ -0,0 +1,135 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::f;
+
+// other
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tinytemplate::TinyTemplate;
+
+/// File extension for templated output
+pub const EXTENSION: &str = "out";
+
+/// Variables available to the template for every record
+#[derive(Serialize)]
+struct RecordContext<'a> {
+    nuclide: &'a str,
+    radiation: &'a str,
+    energy: Option<f32>,
+    intensity: Option<f32>,
+    parent_energy: Option<f32>,
+    norm: f64,
+}
+
+/// Writes every record through a user template to a file at the specified path.
+///
+/// The template is rendered once per record, in order, with the `{nuclide}`,
+/// `{radiation}`, `{energy}`, `{intensity}`, `{parent_energy}`, and `{norm}`
+/// variables. Energies and intensities are in the output units, and missing
+/// values are left empty.
+///
+/// # Arguments
+///
+/// * `template` - Path to the template file.
+/// * `path` - The path where the rendered output should be written.
+/// * `file` - How the output file is opened, e.g. compressed.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write(
+    nuclides: &[NuclideData],
+    template: &Path,
+    path: &Path,
+    file: FileOptions,
+) -> Result<()> {
+    let text = fs::read_to_string(template)
+        .with_context(|| f!("Unable to read template \"{}\"", template.display()))?;
+    let rendered = render(nuclides, &text)
+        .with_context(|| f!("Invalid template \"{}\"", template.display()))?;
+
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.out", file)?;
+    f.write_all(rendered.as_bytes())?;
+    f.finish()
+}
+
+/// Render the template text once for every record, in order
+fn render(nuclides: &[NuclideData], text: &str) -> Result<String> {
+    // output is plain text, so nothing should be HTML escaped
+    let mut tt = TinyTemplate::new();
+    tt.set_default_formatter(&tinytemplate::format_unescaped);
+    tt.add_template("record", text)?;
+
+    let mut output = String::new();
+    for nuclide in nuclides {
+        let norm = nuclide.norm();
+        for record in &nuclide.records {
+            let context = RecordContext {
+                nuclide: &nuclide.name,
+                radiation: record.rad_type.name(),
+                energy: nuclide.in_energy_unit(record.energy),
+                intensity: nuclide.in_units(record.intensity),
+                parent_energy: nuclide.in_energy_unit(record.p_energy),
+                norm,
+            };
+            output += &tt
+                .render("record", &context)
+                .with_context(|| f!("Unable to render a {} record", nuclide.name))?;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nuclide::TaggedRecord;
+    use crate::wrappers::CliRadType;
+    use ntools::iaea::{Nuclide, Record};
+
+    /// Two lines with values that are exact as both f32 and f64
+    fn nuclide_data() -> NuclideData {
+        let nuclide = Nuclide::try_from(&"Co60".to_string()).expect("valid nuclide name");
+        let mut data = NuclideData::new(nuclide);
+        data.name = "Co60".to_string();
+        for (energy, intensity, p_energy) in [(1173.5, 50.0, Some(0.0)), (1332.5, 25.0, None)] {
+            data.records.push(TaggedRecord {
+                rad_type: CliRadType::Gamma,
+                source: Default::default(),
+                record: Record {
+                    energy: Some(energy),
+                    intensity: Some(intensity),
+                    p_energy,
+                    ..Default::default()
+                },
+            });
+        }
+        data
+    }
+
+    #[test]
+    fn csv_template() {
+        let text = include_str!("../templates/csv.txt");
+        let rendered = render(&[nuclide_data()], text).unwrap();
+        assert_eq!(rendered, "Co60,1173.5,50.0\nCo60,1332.5,25.0\n");
+    }
+
+    #[test]
+    fn lines_template() {
+        let text = include_str!("../templates/lines.txt");
+        let rendered = render(&[nuclide_data()], text).unwrap();
+        assert_eq!(
+            rendered,
+            "Co60 gamma: E = 1173.5, I = 50.0, E(parent) = 0.0, total = 0.75 /decay\n\
+             Co60 gamma: E = 1332.5, I = 25.0, E(parent) = , total = 0.75 /decay\n"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+// standard lib
+use std::time::{Duration, Instant};
+
+/// Wall-clock time spent in each phase of a run, reported with --timing
+///
+/// Phases with the same name are added together, so writing every nuclide
+/// separately with --split still gives a single line per output format.
+#[derive(Debug, Default)]
+pub struct Timings {
+    /// Nothing is recorded unless enabled
+    enabled: bool,
+    /// Start of the run, for the total
+    start: Option<Instant>,
+    /// Name and accumulated duration of each phase, in order of first use
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    /// Start timing the run if enabled
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            start: enabled.then(Instant::now),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Run a closure, adding the time it took to the named phase
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = f();
+        self.add(phase, start.elapsed());
+        result
+    }
+
+    /// Add a duration to the named phase
+    fn add(&mut self, phase: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase, duration)),
+        }
+    }
+
+    /// Print the summary table to stderr, regardless of --quiet
+    pub fn report(&self) {
+        let Some(start) = self.start else {
+            return;
+        };
+
+        let width = self
+            .phases
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default()
+            .max(5);
+
+        eprintln!("{:<width$}  {:>10}", "Phase", "Time [ms]");
+        eprintln!("{}", "-".repeat(width + 12));
+        for (name, duration) in &self.phases {
+            eprintln!("{name:<width$}  {:>10.3}", milliseconds(*duration));
+        }
+        eprintln!("{}", "-".repeat(width + 12));
+        eprintln!("{:<width$}  {:>10.3}", "Total", milliseconds(start.elapsed()));
+    }
+}
+
+/// Duration in fractional milliseconds
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
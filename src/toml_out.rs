@@ -0,0 +1,59 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// other
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// File extension for TOML output
+pub const EXTENSION: &str = "toml";
+
+/// TOML has no top-level arrays, so nuclides are an array of tables
+#[derive(Serialize)]
+struct TomlDocument<'a> {
+    nuclide: &'a [NuclideData],
+}
+
+/// Writes the nuclide data to a TOML file at the specified path.
+///
+/// Uses the same structure as the JSON output under `[[nuclide]]` tables.
+/// TOML has no null value, so records missing either an energy or an
+/// intensity are omitted entirely to keep the arrays aligned. Unknown parent
+/// energies are assumed to be the ground state. Raw records are never
+/// included.
+///
+/// # Arguments
+///
+/// * `path` - The path where the TOML data should be written.
+/// * `file` - How the output file is opened, e.g. compressed.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.toml", file)?;
+
+    let complete = nuclides
+        .iter()
+        .map(|n| {
+            let mut n = n.clone();
+            n.records.retain(|r| r.energy.is_some() && r.intensity.is_some());
+            n.all_records.clear();
+            // assume the ground state for unknown parent energies, as in the table
+            for r in n.records.iter_mut() {
+                r.p_energy = r.p_energy.or(Some(0.0));
+            }
+            n
+        })
+        .collect::<Vec<NuclideData>>();
+
+    let document = toml::to_string(&TomlDocument { nuclide: &complete })
+        .context("Unable to serialise to TOML")?;
+    f.write_all(document.as_bytes())?;
+    f.finish()
+}
@@ -0,0 +1,75 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::mcnp::wrap_text;
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::io::Write;
+use std::path::Path;
+
+// neutronics toolbox
+use ntools::utils::{f, ValueExt};
+
+// other
+use anyhow::Result;
+
+const KEV_TO_MEV: f32 = 1.0e-03;
+
+/// File extension for TRIPOLI-4 spectra
+pub const EXTENSION: &str = "t4";
+
+/// Writes the TRIPOLI-4 spectrum blocks to a file at the specified path.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "tripoli.t4", file)?;
+    let blocks = generate_spectrum_blocks(nuclides);
+    f.write_all(blocks.as_bytes())?;
+    f.finish()
+}
+
+/// Make spectrum blocks for every nuclide
+fn generate_spectrum_blocks(nuclides: &[NuclideData]) -> String {
+    nuclides
+        .iter()
+        .map(nuclide_spectrum)
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+/// Make a single spectrum block of energy points and probabilities
+fn nuclide_spectrum(nuclide: &NuclideData) -> String {
+    let filtered_records = nuclide.valid_lines();
+
+    if filtered_records.is_empty() {
+        return f!("// {} records contained no valid decay data\n", nuclide.name);
+    }
+
+    // Create a comment line with nuclide name and normalization factor
+    let comment = f!(
+        "// {} decay data, norm = {} particles/decay",
+        nuclide.name,
+        nuclide.norm().sci(5, 2)
+    );
+
+    // Energy points in MeV
+    let energies = filtered_records
+        .iter()
+        .map(|record| (record.energy.unwrap() * KEV_TO_MEV).sci(5, 2))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    // Associated probabilities
+    let probabilities = filtered_records
+        .iter()
+        .map(|record| (record.intensity.unwrap() * 1e-2).sci(5, 2))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    f!(
+        "{}\nSPECTRUM {} {}\n{}\n{}\nEND_SPECTRUM\n\n",
+        comment,
+        nuclide.name,
+        filtered_records.len(),
+        wrap_text(f!("    {energies}"), 80, "    "),
+        wrap_text(f!("    {probabilities}"), 80, "    ")
+    )
+}
@@ -1,17 +1,30 @@
 //! Basic wrappers for external crate types
 
 // Wrapper for ntools VTK format variants
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 pub enum CliRadType {
     Alpha,
     BetaPlus,
     BetaMinus,
+    #[default]
     Gamma,
     Xray,
     Electron,
+    /// Every radiation type, expanded as soon as the arguments are parsed
+    All,
 }
 
 impl CliRadType {
+    /// Every individual radiation type, i.e. everything but [CliRadType::All]
+    pub const TYPES: [CliRadType; 6] = [
+        CliRadType::Alpha,
+        CliRadType::BetaPlus,
+        CliRadType::BetaMinus,
+        CliRadType::Gamma,
+        CliRadType::Xray,
+        CliRadType::Electron,
+    ];
+
     pub fn name(&self) -> &str {
         match self {
             CliRadType::Alpha => "alpha",
@@ -20,19 +33,56 @@ impl CliRadType {
             CliRadType::Gamma => "gamma",
             CliRadType::Xray => "x-ray",
             CliRadType::Electron => "electron",
+            CliRadType::All => "all",
         }
     }
-}
 
-impl From<CliRadType> for ntools::iaea::RadType {
-    fn from(format: CliRadType) -> Self {
-        match format {
-            CliRadType::Alpha => ntools::iaea::RadType::Alpha,
-            CliRadType::BetaPlus => ntools::iaea::RadType::BetaPlus,
-            CliRadType::BetaMinus => ntools::iaea::RadType::BetaMinus,
-            CliRadType::Gamma => ntools::iaea::RadType::Gamma,
-            CliRadType::Xray => ntools::iaea::RadType::Xray,
-            CliRadType::Electron => ntools::iaea::RadType::Electron,
+    /// Find the radiation type matching a display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::TYPES.iter().find(|r| r.name() == name).copied()
+    }
+
+    /// Replace any [CliRadType::All] with every individual type
+    ///
+    /// The order is otherwise kept, and any type given more than once is only
+    /// included the first time.
+    pub fn expand(rad_types: &[CliRadType]) -> Vec<CliRadType> {
+        let mut expanded = Vec::new();
+        for rad_type in rad_types {
+            let types = match rad_type {
+                CliRadType::All => Self::TYPES.to_vec(),
+                r => vec![*r],
+            };
+            for t in types {
+                if !expanded.contains(&t) {
+                    expanded.push(t);
+                }
+            }
+        }
+        expanded
+    }
+
+    /// Every individual type along with the matching IAEA radiation type
+    ///
+    /// Any [CliRadType::All] is expanded first, so there is no need for it to
+    /// have been expanded elsewhere.
+    pub fn iaea_types(rad_types: &[CliRadType]) -> Vec<(CliRadType, ntools::iaea::RadType)> {
+        Self::expand(rad_types)
+            .into_iter()
+            .filter_map(|r| r.iaea_type().map(|t| (r, t)))
+            .collect()
+    }
+
+    /// IAEA radiation type of an individual type, none for every type
+    fn iaea_type(&self) -> Option<ntools::iaea::RadType> {
+        match self {
+            CliRadType::Alpha => Some(ntools::iaea::RadType::Alpha),
+            CliRadType::BetaPlus => Some(ntools::iaea::RadType::BetaPlus),
+            CliRadType::BetaMinus => Some(ntools::iaea::RadType::BetaMinus),
+            CliRadType::Gamma => Some(ntools::iaea::RadType::Gamma),
+            CliRadType::Xray => Some(ntools::iaea::RadType::Xray),
+            CliRadType::Electron => Some(ntools::iaea::RadType::Electron),
+            CliRadType::All => None,
         }
     }
 }
@@ -45,9 +95,15 @@ impl std::fmt::Display for CliRadType {
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 pub enum Property {
+    #[value(alias = "i")]
     Intensity,
     #[default]
+    #[value(alias = "e")]
     Energy,
+    #[value(alias = "p", alias = "parent")]
+    ParentEnergy,
+    #[value(alias = "o", alias = "original")]
+    None,
 }
 
 impl Property {
@@ -55,6 +111,8 @@ impl Property {
         match self {
             Property::Intensity => "intensity",
             Property::Energy => "energy",
+            Property::ParentEnergy => "parent energy",
+            Property::None => "none",
         }
     }
 }
@@ -64,6 +122,8 @@ impl From<String> for Property {
         match property.to_lowercase().as_str() {
             "i" | "intensity" => Property::Intensity,
             "e" | "energy" => Property::Energy,
+            "p" | "parent" | "parent-energy" => Property::ParentEnergy,
+            "o" | "original" | "none" => Property::None,
             _ => Property::default(),
         }
     }
@@ -74,3 +134,321 @@ impl std::fmt::Display for Property {
         write!(f, "{}", self.name())
     }
 }
+
+/// Orders for the list of nuclides
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum NuclideOrder {
+    /// Alphabetical by element symbol, then by mass number
+    #[default]
+    Name,
+    /// Atomic number, then mass number
+    Z,
+    /// Mass number, then atomic number
+    #[value(alias = "a")]
+    Mass,
+}
+
+impl NuclideOrder {
+    pub fn name(&self) -> &str {
+        match self {
+            NuclideOrder::Name => "name",
+            NuclideOrder::Z => "z",
+            NuclideOrder::Mass => "mass",
+        }
+    }
+}
+
+impl std::fmt::Display for NuclideOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// MCNP SI card distribution options
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum McnpDist {
+    /// Discrete source variable values
+    #[default]
+    #[value(name = "L")]
+    Discrete,
+    /// Histogram bin upper boundaries
+    #[value(name = "H")]
+    Histogram,
+    /// Points where a probability density is defined
+    #[value(name = "A")]
+    Points,
+}
+
+impl McnpDist {
+    pub fn letter(&self) -> &str {
+        match self {
+            McnpDist::Discrete => "L",
+            McnpDist::Histogram => "H",
+            McnpDist::Points => "A",
+        }
+    }
+}
+
+impl std::fmt::Display for McnpDist {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// Columns available in the text table
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Column {
+    Parent,
+    Mode,
+    Daughter,
+    #[value(alias = "br")]
+    Branching,
+    Energy,
+    Intensity,
+}
+
+impl Column {
+    pub fn name(&self) -> &str {
+        match self {
+            Column::Parent => "parent",
+            Column::Mode => "mode",
+            Column::Daughter => "daughter",
+            Column::Branching => "branching",
+            Column::Energy => "energy",
+            Column::Intensity => "intensity",
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Predefined photon energy group structures
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupStructure {
+    /// ORIGEN 18-group gamma structure
+    #[default]
+    #[value(name = "origen-18", alias = "18")]
+    Origen18,
+}
+
+impl GroupStructure {
+    pub fn name(&self) -> &str {
+        match self {
+            GroupStructure::Origen18 => "origen-18",
+        }
+    }
+}
+
+impl std::fmt::Display for GroupStructure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Colour themes for the table
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum TableTheme {
+    /// Standard colours for dark terminals
+    #[default]
+    Default,
+    /// No colours, only bold and italic effects
+    Mono,
+    /// Bright colours for maximum contrast
+    HighContrast,
+}
+
+impl TableTheme {
+    pub fn name(&self) -> &str {
+        match self {
+            TableTheme::Default => "default",
+            TableTheme::Mono => "mono",
+            TableTheme::HighContrast => "high-contrast",
+        }
+    }
+}
+
+impl std::fmt::Display for TableTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Units for reporting intensities
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum IntensityUnits {
+    /// Percent of decays
+    #[default]
+    Percent,
+    /// Fraction per decay
+    Fraction,
+}
+
+impl IntensityUnits {
+    pub fn name(&self) -> &str {
+        match self {
+            IntensityUnits::Percent => "percent",
+            IntensityUnits::Fraction => "fraction",
+        }
+    }
+
+    /// Short unit label for headers
+    pub fn symbol(&self) -> &str {
+        match self {
+            IntensityUnits::Percent => "%",
+            IntensityUnits::Fraction => "-",
+        }
+    }
+
+    /// Factor to convert an intensity from percent
+    pub fn scale(&self) -> f32 {
+        match self {
+            IntensityUnits::Percent => 1.0,
+            IntensityUnits::Fraction => 1e-2,
+        }
+    }
+}
+
+impl std::fmt::Display for IntensityUnits {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Units of energy on the command line and in outputs
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum EnergyUnit {
+    #[value(name = "eV")]
+    Ev,
+    #[default]
+    #[value(name = "keV")]
+    Kev,
+    #[value(name = "MeV")]
+    Mev,
+}
+
+impl EnergyUnit {
+    pub fn name(&self) -> &str {
+        match self {
+            EnergyUnit::Ev => "eV",
+            EnergyUnit::Kev => "keV",
+            EnergyUnit::Mev => "MeV",
+        }
+    }
+
+    /// Factor to convert an energy in these units to keV
+    pub fn kev_factor(&self) -> f32 {
+        match self {
+            EnergyUnit::Ev => 1e-3,
+            EnergyUnit::Kev => 1.0,
+            EnergyUnit::Mev => 1e3,
+        }
+    }
+
+    /// Convert an energy in these units to keV
+    pub fn to_kev(&self, energy: f32) -> f32 {
+        energy * self.kev_factor()
+    }
+
+    /// Convert an energy in keV to these units
+    pub fn from_kev(&self, energy: f32) -> f32 {
+        energy / self.kev_factor()
+    }
+}
+
+impl std::fmt::Display for EnergyUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Output file formats, as an alternative to the individual flags
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[value(alias = "txt")]
+    Text,
+    Json,
+    Ndjson,
+    FlatJson,
+    #[value(alias = "yml")]
+    Yaml,
+    Toml,
+    Html,
+    Mcnp,
+    Serpent,
+    #[value(alias = "t4")]
+    Tripoli,
+    Fluka,
+    Origen,
+    Geant4,
+    Npz,
+    #[value(alias = "export-csv")]
+    Records,
+    Tsv,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::FlatJson => "flat-json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Html => "html",
+            OutputFormat::Mcnp => "mcnp",
+            OutputFormat::Serpent => "serpent",
+            OutputFormat::Tripoli => "tripoli",
+            OutputFormat::Fluka => "fluka",
+            OutputFormat::Origen => "origen",
+            OutputFormat::Geant4 => "geant4",
+            OutputFormat::Npz => "npz",
+            OutputFormat::Records => "records",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Where a set of decay records came from
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    /// Pre-fetched data bundled with ntools
+    #[default]
+    Local,
+    /// Fetched directly from the IAEA API
+    Iaea,
+}
+
+impl DataSource {
+    pub fn name(&self) -> &str {
+        match self {
+            DataSource::Local => "local",
+            DataSource::Iaea => "iaea",
+        }
+    }
+
+    /// Find the data source matching a display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        [DataSource::Local, DataSource::Iaea]
+            .into_iter()
+            .find(|s| s.name() == name)
+    }
+}
+
+impl std::fmt::Display for DataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
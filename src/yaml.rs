@@ -0,0 +1,31 @@
+// internal
+use crate::{create_file_with_fallback, FileOptions};
+use crate::nuclide::NuclideData;
+
+// standard lib
+use std::path::Path;
+
+// other
+use anyhow::{Context, Result};
+
+/// File extension for YAML output
+pub const EXTENSION: &str = "yaml";
+
+/// Writes the nuclide data to a YAML file at the specified path.
+///
+/// Uses the same structure as the JSON output, with missing values written
+/// explicitly as `null`.
+///
+/// # Arguments
+///
+/// * `path` - The path where the YAML data should be written.
+/// * `file` - How the output file is opened, e.g. compressed.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub fn write(nuclides: &[NuclideData], path: &Path, file: FileOptions) -> Result<()> {
+    let mut f = create_file_with_fallback(path, EXTENSION, "decay_data.yaml", file)?;
+    serde_yaml::to_writer(&mut f, &nuclides).context("Unable to serialise to YAML")?;
+    f.finish()
+}